@@ -0,0 +1,112 @@
+//!
+//! Benchmarks of the throughput of tokenizing, counting and sorting, in tokens and bytes per second, on
+//! "Pride and Prejudice" and on synthetic corpora of ASCII and non-ASCII words. Tokenizing is measured both for
+//! [`Tokenizer`] and for the [`ByteTokenizer`] used for files mapped into memory.
+//!
+//! Run with `cargo bench`, or `cargo bench -- tokenize` for a single group.
+
+use std::fs;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use word_frequency::frequency::FrequencyCounter;
+use word_frequency::mapped::ByteTokenizer;
+use word_frequency::tokenizer::Tokenizer;
+
+const PRIDE_AND_PREJUDICE: &str = "pride-and-prejudice.txt";
+const STOP_WORDS: &str = "stop-words.txt";
+const SYNTHETIC_WORDS: usize = 200_000;
+
+const ASCII_VOCABULARY: [&str; 16] = [
+    "the", "cat", "sat", "on", "mat", "and", "dog", "ran", "away", "from", "house", "it's", "42", "quickly", "brown",
+    "fox",
+];
+const UNICODE_VOCABULARY: [&str; 16] = [
+    "räksmörgås", "über", "naïve", "café", "straße", "日本語", "ελληνικά", "русский", "año", "crème", "brûlée", "façade",
+    "smörgåsbord", "Ω", "señor", "øl",
+];
+
+/// Gives the corpora to benchmark, as pairs of a name and the lowercased text.
+fn corpora() -> Vec<(&'static str, String)> {
+    let pride_and_prejudice = fs::read_to_string(PRIDE_AND_PREJUDICE).expect("the text of Pride and Prejudice");
+    vec![
+        ("pride-and-prejudice", pride_and_prejudice.to_lowercase()),
+        ("synthetic-ascii", synthetic_text(&ASCII_VOCABULARY, SYNTHETIC_WORDS)),
+        ("synthetic-unicode", synthetic_text(&UNICODE_VOCABULARY, SYNTHETIC_WORDS)),
+    ]
+}
+
+/// Makes a text of the given number of words drawn from the vocabulary, with some punctuation, using a linear
+/// congruential generator so that the text is the same in every run.
+fn synthetic_text(vocabulary: &[&str], words: usize) -> String {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut text = String::new();
+    for i in 0..words {
+        state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        text.push_str(&vocabulary[(state >> 33) as usize % vocabulary.len()].to_lowercase());
+        text.push_str(match i % 12 {
+            11 => ".\n",
+            5 => ", ",
+            _ => " ",
+        });
+    }
+    text
+}
+
+fn throughput(text: &str) -> Throughput {
+    Throughput::ElementsAndBytes {
+        elements: Tokenizer::new(text).count() as u64,
+        bytes: text.len() as u64,
+    }
+}
+
+fn bench_tokenize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tokenize");
+    for (name, text) in corpora() {
+        group.throughput(throughput(&text));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &text, |b, text| {
+            b.iter(|| Tokenizer::new(black_box(text)).count())
+        });
+        group.bench_with_input(BenchmarkId::new("bytes", name), &text, |b, text| {
+            b.iter(|| ByteTokenizer::new(black_box(text.as_bytes())).unwrap().count())
+        });
+    }
+    group.finish();
+}
+
+fn bench_count_frequencies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("count_frequencies");
+    for (name, text) in corpora() {
+        group.throughput(throughput(&text));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &text, |b, text| {
+            b.iter(|| {
+                let mut frequency_counter = FrequencyCounter::new(black_box(text));
+                frequency_counter.read_stop_words(STOP_WORDS).unwrap();
+                frequency_counter.count_frequencies()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("interned", name), &text, |b, text| {
+            b.iter(|| {
+                let mut frequency_counter = FrequencyCounter::new(black_box(text));
+                frequency_counter.read_stop_words(STOP_WORDS).unwrap();
+                frequency_counter.count_word_counts(1)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_sort_frequencies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sort_frequencies");
+    for (name, text) in corpora() {
+        let word_frequencies = FrequencyCounter::new(&text).count_frequencies();
+        group.throughput(Throughput::Elements(word_frequencies.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &word_frequencies, |b, word_frequencies| {
+            b.iter(|| FrequencyCounter::sort_frequencies(black_box(word_frequencies)).len())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_tokenize, bench_count_frequencies, bench_sort_frequencies);
+criterion_main!(benches);
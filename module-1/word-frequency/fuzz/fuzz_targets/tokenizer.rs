@@ -0,0 +1,27 @@
+//!
+//! Fuzzes `Tokenizer::next_token()` with arbitrary strings. Run with `cargo fuzz run tokenizer` in the
+//! `word-frequency` directory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use word_frequency::tokenizer::{Token, Tokenizer};
+
+fuzz_target!(|input: &str| {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokenizer = Tokenizer::new(input);
+    let mut start = tokenizer.position();
+    while let Some(token) = tokenizer.next_token() {
+        let end = tokenizer.position();
+        assert!(end > start, "the tokenizer did not advance at position {}", start);
+        let text: String = chars[start..end].iter().collect();
+        match token {
+            Token::Word(word) | Token::Numeral(word) => assert_eq!(word, text),
+            Token::Number(number) => assert_eq!(text.parse::<i64>(), Ok(number)),
+            Token::Whitespace => assert!(text.chars().all(char::is_whitespace)),
+            Token::Punctuation(c) => assert_eq!(c.to_string(), text),
+        }
+        start = end;
+    }
+    assert_eq!(start, chars.len());
+});
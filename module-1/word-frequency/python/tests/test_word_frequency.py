@@ -0,0 +1,97 @@
+"""Tests of the Python bindings, run from the crate directory with the extension module installed:
+
+    python -m unittest discover python/tests
+"""
+
+import threading
+import unittest
+
+import word_frequency
+
+
+class TokenizerTest(unittest.TestCase):
+    def test_tokens(self):
+        tokens = list(word_frequency.Tokenizer("Hi, Bob 42 times ½"))
+        self.assertEqual(
+            tokens,
+            [
+                ("word", "Hi"),
+                ("punctuation", ","),
+                ("whitespace", None),
+                ("word", "Bob"),
+                ("whitespace", None),
+                ("number", 42),
+                ("whitespace", None),
+                ("word", "times"),
+                ("whitespace", None),
+                ("numeral", "½"),
+            ],
+        )
+
+    def test_position(self):
+        tokenizer = word_frequency.Tokenizer("Hi, Bob")
+        self.assertEqual(tokenizer.position, 0)
+        next(tokenizer)
+        self.assertEqual(tokenizer.position, 2)
+
+
+class FrequencyCounterTest(unittest.TestCase):
+    def test_count_frequencies(self):
+        counter = word_frequency.FrequencyCounter("the cat and the hat")
+        self.assertEqual(counter.count_frequencies(), {"the": 2, "cat": 1, "and": 1, "hat": 1})
+
+    def test_stop_words(self):
+        counter = word_frequency.FrequencyCounter("the cat and the hat")
+        counter.stop_words = ["the", "and"]
+        self.assertEqual(counter.stop_words, ["and", "the"])
+        self.assertEqual(counter.count_frequencies(), {"cat": 1, "hat": 1})
+
+    def test_count_ngrams(self):
+        counter = word_frequency.FrequencyCounter("to be or not to be")
+        self.assertEqual(counter.count_ngrams(2)["to be"], 2)
+
+    def test_from_file(self):
+        counter = word_frequency.FrequencyCounter.from_file("poem.txt")
+        counter.read_stop_words("stop-words.txt")
+        frequencies = counter.count_frequencies()
+        self.assertNotIn("the", frequencies)
+        self.assertTrue(all(word == word.lower() for word in frequencies))
+
+    def test_count_in_threads(self):
+        results = []
+
+        def count():
+            counter = word_frequency.FrequencyCounter.from_file("pride-and-prejudice.txt")
+            results.append(counter.count_frequencies())
+
+        threads = [threading.Thread(target=count) for _ in range(2)]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+        self.assertEqual(results[0], results[1])
+
+    def test_errors(self):
+        with self.assertRaises(OSError):
+            word_frequency.FrequencyCounter.from_file("no-such-file.txt")
+        with self.assertRaises(OSError):
+            word_frequency.FrequencyCounter().read_stop_words("no-such-file.txt")
+
+
+class SortFrequenciesTest(unittest.TestCase):
+    def test_tuples(self):
+        frequencies = {"cat": 1, "the": 2, "and": 1}
+        self.assertEqual(word_frequency.sort_frequencies(frequencies), [("the", 2), ("and", 1), ("cat", 1)])
+
+    def test_dicts(self):
+        sorted_frequencies = word_frequency.sort_frequencies({"the": 2, "cat": 1, "hat": 1}, dicts=True)
+        self.assertEqual(sorted_frequencies[0], {"word": "the", "count": 2, "share": 0.5, "relative": 1.0})
+        self.assertEqual(sorted_frequencies[2]["word"], "hat")
+        self.assertEqual(sorted_frequencies[2]["relative"], 0.5)
+
+    def test_empty(self):
+        self.assertEqual(word_frequency.sort_frequencies({}), [])
+
+
+if __name__ == "__main__":
+    unittest.main()
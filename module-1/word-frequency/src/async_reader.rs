@@ -0,0 +1,257 @@
+//!
+//! Contains code to count words from an asynchronous stream, like a network connection, with tokio's `AsyncRead`,
+//! without blocking the runtime while waiting for more input. Needs the `async` feature.
+//!
+//! The input is counted a piece at a time as it arrives, so the whole input never needs to be kept in memory. It is
+//! converted to lower case like in [`FrequencyCounter::from_reader()`], but it must be plain, uncompressed text.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::frequency::FrequencyCounter;
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let mut frequency_counter = FrequencyCounter::new("");
+//! frequency_counter.read_stop_words("stop-words.txt").unwrap();
+//! let stream: &[u8] = b"The cat and the hat";
+//! let word_frequencies = frequency_counter.count_async(stream, 1).await.unwrap();
+//! assert_eq!(word_frequencies["cat"], 1);
+//! assert!(!word_frequencies.contains_key("the"));
+//! # });
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use rustc_hash::FxHashSet;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::compression::Compression;
+use crate::error::{Error, Result};
+use crate::frequency::{FrequencyCounter, NgramCounter};
+use crate::mapped::{self, ByteTokenizer};
+use crate::phrases::Phrases;
+use crate::vocabulary::WordCounts;
+
+/// The number of bytes read from the stream at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// The number of bytes needed to recognize compressed input, see [`Compression::detect()`].
+const HEADER_LENGTH: usize = 4;
+
+/// Counts the words or n-grams of input given a piece at a time.
+#[derive(Debug)]
+pub struct StreamCounter {
+    counter: NgramCounter,
+    word_counts: WordCounts,
+    /// The input after the last character that may end a token, which is counted when more input arrives
+    pending: Vec<u8>,
+    /// Whether the start of the input has been checked for the magic bytes of compressed input
+    checked: bool,
+}
+
+impl StreamCounter {
+    /// Creates a new `StreamCounter` that counts sequences of `n` words, ignoring the given stop words like
+    /// [`FrequencyCounter::count_ngrams()`].
+    pub fn new(stop_words: &HashSet<String>, n: usize) -> Self {
+        Self::with_stop_word_set(Arc::new(stop_words.iter().cloned().collect()), n)
+    }
+
+    /// Works like [`Self::new()`], but shares the stop words of a [`FrequencyCounter`] instead of copying them.
+    fn with_stop_word_set(stop_words: Arc<FxHashSet<String>>, n: usize) -> Self {
+        Self {
+            counter: NgramCounter::new(stop_words, n),
+            word_counts: WordCounts::new(),
+            pending: Vec::new(),
+            checked: false,
+        }
+    }
+
+    /// Counts the given multi-word expressions as single words, like [`FrequencyCounter::set_phrases()`].
+    pub fn with_phrases(self, phrases: &Phrases) -> Self {
+        self.with_shared_phrases(Arc::new(phrases.clone()))
+    }
+
+    /// Works like [`Self::with_phrases()`], but shares the expressions of a [`FrequencyCounter`] instead of copying
+    /// them.
+    fn with_shared_phrases(mut self, phrases: Arc<Phrases>) -> Self {
+        self.counter = self.counter.with_phrases(Some(phrases));
+        self
+    }
+
+    /// Counts the next piece of input. A word or a character may be split between pieces, so the input is only
+    /// counted up to the last space or punctuation, and the rest is kept until the next piece or [`Self::finish()`].
+    ///
+    /// Gives an [`Error::Decode`] if the input is not valid UTF-8 or is compressed.
+    pub fn add(&mut self, input: &[u8]) -> Result<()> {
+        // The pending input has no place to split it, except maybe for a character that the new piece completes, so
+        // only the new piece needs to be searched, which keeps reading long words or lines linear
+        let start = if self.checked { self.pending.len().saturating_sub(3) } else { 0 };
+        self.pending.extend_from_slice(input);
+        if !self.checked {
+            if self.pending.len() < HEADER_LENGTH {
+                return Ok(());
+            }
+            self.check_compression()?;
+        }
+        if let Some(end) = mapped::last_token_end(&self.pending[start..]) {
+            let rest = self.pending.split_off(start + end);
+            let complete = std::mem::replace(&mut self.pending, rest);
+            self.count(complete)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the given stream to its end and counts it, giving up the thread while waiting for input.
+    ///
+    /// Gives an [`Error::Io`] if reading fails, and an [`Error::Decode`] like [`Self::add()`].
+    pub async fn read<R: AsyncRead + Unpin>(&mut self, mut reader: R) -> Result<()> {
+        let mut buffer = vec![0; CHUNK_SIZE];
+        loop {
+            let length = reader.read(&mut buffer).await.map_err(Error::reading)?;
+            if length == 0 {
+                return Ok(());
+            }
+            self.add(&buffer[..length])?;
+        }
+    }
+
+    /// Counts the rest of the input and gives the counts.
+    ///
+    /// Gives an [`Error::Decode`] if the input ends in the middle of a character.
+    pub fn finish(mut self) -> Result<WordCounts> {
+        // Only a stream shorter than a header is checked here, since the pending input is not at the start otherwise
+        if !self.checked {
+            self.check_compression()?;
+        }
+        let rest = std::mem::take(&mut self.pending);
+        self.count(rest)?;
+        self.counter.finish(&mut self.word_counts);
+        Ok(self.word_counts)
+    }
+
+    fn check_compression(&mut self) -> Result<()> {
+        self.checked = true;
+        if Compression::detect(&self.pending) != Compression::None {
+            return Err(Error::Decode {
+                path: None,
+                message: "compressed input cannot be read asynchronously".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn count(&mut self, input: Vec<u8>) -> Result<()> {
+        let mut tokenizer = ByteTokenizer::new(input)?;
+        self.counter.add_all(|word| tokenizer.read_into(word), &mut self.word_counts);
+        Ok(())
+    }
+}
+
+impl FrequencyCounter {
+    /// Works like [`Self::count_ngrams()`], but counts the words of the given stream instead of the input of this
+    /// `FrequencyCounter`, reading it asynchronously, see [`StreamCounter`].
+    ///
+    /// Gives an [`Error::Io`] if reading fails, and an [`Error::Decode`] if the input is not valid UTF-8 or is
+    /// compressed.
+    pub async fn count_async<R: AsyncRead + Unpin>(&self, reader: R, n: usize) -> Result<HashMap<String, usize>> {
+        let mut stream_counter = StreamCounter::with_stop_word_set(Arc::clone(self.stop_word_set()), n);
+        if let Some(phrases) = self.shared_phrases() {
+            stream_counter = stream_counter.with_shared_phrases(Arc::clone(phrases));
+        }
+        stream_counter.read(reader).await?;
+        let mut ngram_frequencies = HashMap::new();
+        stream_counter.finish()?.add_to(&mut ngram_frequencies);
+        Ok(ngram_frequencies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pieces_count_like_whole_input() {
+        let text = "Lady Catherine's daughter, Miss de Bourgh, and Lady Lucas. Über naïve café!";
+        for n in 1..=3 {
+            let expected = FrequencyCounter::new(&text.to_lowercase()).count_ngrams(n);
+            for piece_length in [1, 2, 3, 7, 100] {
+                let mut stream_counter = StreamCounter::new(&HashSet::new(), n);
+                for piece in text.as_bytes().chunks(piece_length) {
+                    stream_counter.add(piece).unwrap();
+                }
+                let mut actual = HashMap::new();
+                stream_counter.finish().unwrap().add_to(&mut actual);
+                assert_eq!(actual, expected, "n = {}, pieces of {} bytes", n, piece_length);
+            }
+        }
+    }
+
+    #[test]
+    fn test_phrases_split_between_pieces() {
+        let phrases = Phrases::new(["new york", "new york city"]).unwrap();
+        let mut stream_counter = StreamCounter::new(&HashSet::new(), 1).with_phrases(&phrases);
+        for piece in ["in new", " york", " city. new ", "york"] {
+            stream_counter.add(piece.as_bytes()).unwrap();
+        }
+        let word_counts = stream_counter.finish().unwrap();
+        assert_eq!(word_counts.count("new york city"), 1);
+        assert_eq!(word_counts.count("new york"), 1);
+        assert_eq!(word_counts.count("new"), 0);
+    }
+
+    #[test]
+    fn test_pieces_are_split_at_other_whitespace_and_punctuation() {
+        let text = "東京　大阪、京都。ΟΔΟΣ·ΟΔΟΣ ½ Ⅻ";
+        let expected = FrequencyCounter::new(&text.to_lowercase()).count_ngrams(1);
+        for piece_length in [1, 2, 5] {
+            let mut stream_counter = StreamCounter::new(&HashSet::new(), 1);
+            let mut pending = 0;
+            for piece in text.as_bytes().chunks(piece_length) {
+                stream_counter.add(piece).unwrap();
+                pending = pending.max(stream_counter.pending.len());
+            }
+            let mut actual = HashMap::new();
+            stream_counter.finish().unwrap().add_to(&mut actual);
+            assert_eq!(actual, expected, "pieces of {} bytes", piece_length);
+            assert!(pending < "ΟΔΟΣ·ΟΔΟΣ".len() + piece_length, "pieces of {} bytes", piece_length);
+        }
+    }
+
+    #[test]
+    fn test_last_word_like_compression_header() {
+        let mut stream_counter = StreamCounter::new(&HashSet::new(), 1);
+        stream_counter.add(b"hello world BZh91").unwrap();
+        let word_counts = stream_counter.finish().unwrap();
+        assert_eq!(word_counts.count("bzh"), 1);
+        assert_eq!(word_counts.count("hello"), 1);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let mut stream_counter = StreamCounter::new(&HashSet::new(), 1);
+        stream_counter.add(b"caf\xc3").unwrap();
+        assert!(matches!(stream_counter.finish(), Err(Error::Decode { .. })));
+        let mut stream_counter = StreamCounter::new(&HashSet::new(), 1);
+        stream_counter.add(&[0x1f, 0x8b]).unwrap();
+        assert!(matches!(stream_counter.finish(), Err(Error::Decode { .. })));
+        let mut stream_counter = StreamCounter::new(&HashSet::new(), 1);
+        stream_counter.add(&[0x1f, 0x8b]).unwrap();
+        assert!(matches!(stream_counter.add(&[8, 0]), Err(Error::Decode { .. })));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_count_async_from_duplex_stream() {
+        let (mut writer, reader) = tokio::io::duplex(16);
+        let write = tokio::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+            for _ in 0..100 {
+                writer.write_all(b"to be or not to be, ").await.unwrap();
+            }
+        });
+        let word_frequencies = FrequencyCounter::new("").count_async(reader, 2).await.unwrap();
+        write.await.unwrap();
+        assert_eq!(word_frequencies["to be"], 200);
+        assert_eq!(word_frequencies["not to"], 100);
+        assert!(!word_frequencies.contains_key("be to"));
+    }
+}
@@ -0,0 +1,206 @@
+//!
+//! Contains code to count characters, classes of characters, or byte values instead of words, e.g., for letter
+//! frequencies when breaking a cipher or for finding unexpected bytes when debugging an encoding problem.
+//!
+//! The counts are given as a mapping from a printable name of each character, class or byte to its number of
+//! occurrences, like the word counts from [`FrequencyCounter::count_frequencies()`], so that they can be sorted with
+//! [`FrequencyCounter::sort_frequencies()`] and printed in the same way.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::characters::{self, Unit};
+//! use word_frequency::input_format::InputFormat;
+//!
+//! let text = "Hi, Bob!\n";
+//! let character_frequencies = characters::count(Unit::Characters, text.as_bytes(), InputFormat::Text).unwrap();
+//! assert_eq!(character_frequencies["b"], 1);
+//! assert_eq!(character_frequencies["B"], 1);
+//! assert_eq!(character_frequencies["\\n"], 1);
+//! let class_frequencies = characters::count(Unit::Classes, text.as_bytes(), InputFormat::Text).unwrap();
+//! assert_eq!(class_frequencies["lowercase"], 3);
+//! assert_eq!(class_frequencies["punctuation"], 2);
+//! let byte_frequencies = characters::count(Unit::Bytes, text.as_bytes(), InputFormat::Text).unwrap();
+//! assert_eq!(byte_frequencies["0x42 'B'"], 1);
+//! assert_eq!(byte_frequencies["0x0A"], 1);
+//! ```
+//!
+//! [`FrequencyCounter::count_frequencies()`]: crate::frequency::FrequencyCounter::count_frequencies()
+//! [`FrequencyCounter::sort_frequencies()`]: crate::frequency::FrequencyCounter::sort_frequencies()
+
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+
+use crate::error::{Error, Result};
+use crate::input_format::InputFormat;
+
+/// The units that can be counted instead of words.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, ValueEnum)]
+pub enum Unit {
+    /// Unicode characters, with upper and lower case counted separately
+    Characters,
+    /// Classes of characters, like letters, digits and punctuation
+    Classes,
+    /// Byte values, without decoding the input as UTF-8
+    Bytes,
+}
+
+/// Counts the given unit in the given input, which must be valid UTF-8 unless bytes are counted. Characters and
+/// classes are counted in the text extracted from the given format, while bytes are counted in the input as it is.
+///
+/// Gives an [`Error::Decode`] if the input is not valid UTF-8.
+pub fn count(unit: Unit, input: &[u8], format: InputFormat) -> Result<HashMap<String, usize>> {
+    match unit {
+        Unit::Characters => Ok(count_characters(&format.extract_text(decode(input)?))),
+        Unit::Classes => Ok(count_classes(&format.extract_text(decode(input)?))),
+        Unit::Bytes => Ok(count_bytes(input)),
+    }
+}
+
+/// Counts the characters of the given text, see [`character_name()`] for how they are named.
+pub fn count_characters(text: &str) -> HashMap<String, usize> {
+    let mut character_frequencies = HashMap::new();
+    for c in text.chars() {
+        *character_frequencies.entry(character_name(c)).or_insert(0) += 1;
+    }
+    character_frequencies
+}
+
+/// Counts the classes of the characters of the given text, see [`character_class()`].
+pub fn count_classes(text: &str) -> HashMap<String, usize> {
+    let mut class_frequencies = HashMap::new();
+    for c in text.chars() {
+        *class_frequencies.entry(character_class(c).to_string()).or_insert(0) += 1;
+    }
+    class_frequencies
+}
+
+/// Counts the byte values of the given input. Each byte is named by its hexadecimal value, followed by the ASCII
+/// character in quotes if it is printable, e.g., `0x41 'A'` or `0x0A`.
+pub fn count_bytes(input: &[u8]) -> HashMap<String, usize> {
+    let mut counts = [0usize; 256];
+    for &b in input {
+        counts[b as usize] += 1;
+    }
+    (0..=u8::MAX)
+        .filter(|&b| counts[b as usize] > 0)
+        .map(|b| {
+            let name = if b.is_ascii_graphic() || b == b' ' {
+                format!("0x{:02X} '{}'", b, b as char)
+            } else {
+                format!("0x{:02X}", b)
+            };
+            (name, counts[b as usize])
+        })
+        .collect()
+}
+
+/// Gives a printable name of a character: the character itself if it is visible, the usual escape for tab, newline
+/// and carriage return, `space` for a space, and the code point, e.g., `U+00A0`, for other invisible characters.
+pub fn character_name(c: char) -> String {
+    match c {
+        ' ' => "space".to_string(),
+        '\t' => "\\t".to_string(),
+        '\n' => "\\n".to_string(),
+        '\r' => "\\r".to_string(),
+        c if c.is_whitespace() || c.is_control() || is_invisible(c) => format!("U+{:04X}", c as u32),
+        c => c.to_string(),
+    }
+}
+
+/// Gives the class of a character: `uppercase`, `lowercase`, `other letter` for letters without case, `digit` for
+/// ASCII digits, `other numeric`, `whitespace`, `punctuation` for ASCII punctuation, `control` or `other`.
+pub fn character_class(c: char) -> &'static str {
+    if c.is_uppercase() {
+        "uppercase"
+    } else if c.is_lowercase() {
+        "lowercase"
+    } else if c.is_alphabetic() {
+        "other letter"
+    } else if c.is_ascii_digit() {
+        "digit"
+    } else if c.is_numeric() {
+        "other numeric"
+    } else if c.is_whitespace() {
+        "whitespace"
+    } else if c.is_ascii_punctuation() {
+        "punctuation"
+    } else if c.is_control() {
+        "control"
+    } else {
+        "other"
+    }
+}
+
+/// Tells if a character is a format character that is not shown, like a zero width space or a byte order mark.
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{00AD}' | '\u{200B}'..='\u{200F}' | '\u{2028}'..='\u{202E}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}')
+}
+
+fn decode(input: &[u8]) -> Result<&str> {
+    std::str::from_utf8(input).map_err(|e| Error::Decode {
+        path: None,
+        message: format!("invalid UTF-8: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_characters() {
+        let character_frequencies = count_characters("Aaä a\u{feff}\t");
+        assert_eq!(character_frequencies["a"], 2);
+        assert_eq!(character_frequencies["A"], 1);
+        assert_eq!(character_frequencies["ä"], 1);
+        assert_eq!(character_frequencies["space"], 1);
+        assert_eq!(character_frequencies["U+FEFF"], 1);
+        assert_eq!(character_frequencies["\\t"], 1);
+        assert_eq!(character_frequencies.len(), 6);
+    }
+
+    #[test]
+    fn test_character_class() {
+        let classes: Vec<&str> = "Aa日7½ ,\u{7}€".chars().map(character_class).collect();
+        assert_eq!(
+            classes,
+            [
+                "uppercase",
+                "lowercase",
+                "other letter",
+                "digit",
+                "other numeric",
+                "whitespace",
+                "punctuation",
+                "control",
+                "other"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_count_bytes() {
+        let byte_frequencies = count_bytes(b"aa\xff\x00 ");
+        assert_eq!(byte_frequencies["0x61 'a'"], 2);
+        assert_eq!(byte_frequencies["0xFF"], 1);
+        assert_eq!(byte_frequencies["0x00"], 1);
+        assert_eq!(byte_frequencies["0x20 ' '"], 1);
+        assert_eq!(byte_frequencies.len(), 4);
+    }
+
+    #[test]
+    fn test_count_with_format() {
+        let class_frequencies = count(Unit::Classes, b"<p>Hi</p>", InputFormat::Html).unwrap();
+        assert_eq!(class_frequencies.get("punctuation"), None);
+        let byte_frequencies = count(Unit::Bytes, b"<p>Hi</p>", InputFormat::Html).unwrap();
+        assert_eq!(byte_frequencies["0x3C '<'"], 2);
+    }
+
+    #[test]
+    fn test_count_invalid_utf8() {
+        assert!(matches!(count(Unit::Characters, b"caf\xe9", InputFormat::Text), Err(Error::Decode { .. })));
+        assert_eq!(count(Unit::Bytes, b"caf\xe9", InputFormat::Text).unwrap().len(), 4);
+    }
+}
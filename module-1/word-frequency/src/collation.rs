@@ -0,0 +1,166 @@
+//!
+//! Contains code to compare words alphabetically, for sorting words alphabetically and for breaking ties between
+//! words with the same count.
+//!
+//! By default, words are compared by their bytes, which is fast and deterministic, but sorts all letters with
+//! diacritics after `z`, e.g., "ähnlich" after "zebra". Words can instead be compared with the Unicode collation
+//! algorithm, optionally with the rules of a locale, like Swedish sorting "ä" after "z" but German sorting it with
+//! "a", and case can be ignored. Words that the collation considers equal, e.g., "Cat" and "cat" when ignoring case,
+//! are compared by their bytes, so the order is always the same.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::cmp::Ordering;
+//! use word_frequency::collation::Collation;
+//!
+//! assert_eq!(Collation::default().compare("ähnlich", "zebra"), Ordering::Greater);
+//! let german = Collation::unicode(Some("de"), false).unwrap();
+//! assert_eq!(german.compare("ähnlich", "zebra"), Ordering::Less);
+//! let swedish = Collation::unicode(Some("sv"), false).unwrap();
+//! assert_eq!(swedish.compare("ähnlich", "zebra"), Ordering::Greater);
+//!
+//! let mut words = vec!["b", "Ab", "aa"];
+//! words.sort_by(|a, b| Collation::bytes(true).compare(a, b));
+//! assert_eq!(words, ["aa", "Ab", "b"]);
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use clap::ValueEnum;
+use icu_collator::{Collator, CollatorOptions, Strength};
+
+use crate::error::{Error, Result};
+use crate::frequency::Count;
+
+/// The kinds of collation that words can be compared with.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, ValueEnum)]
+pub enum CollationKind {
+    /// By the bytes of the words, i.e., by Unicode code points
+    #[default]
+    Bytes,
+    /// By the Unicode collation algorithm, with the rules of a locale if one is given
+    Unicode,
+}
+
+/// Compares words, see the [module documentation](self).
+#[derive(Clone, Default)]
+pub struct Collation {
+    /// The collator of the Unicode collation algorithm, or `None` to compare bytes
+    collator: Option<Arc<Collator>>,
+    ignore_case: bool,
+}
+
+impl Collation {
+    /// Creates a `Collation` that compares the bytes of words, first converted to lower case if `ignore_case` is true.
+    pub fn bytes(ignore_case: bool) -> Self {
+        Self {
+            collator: None,
+            ignore_case,
+        }
+    }
+
+    /// Creates a `Collation` that compares words with the Unicode collation algorithm, with the rules of the given
+    /// locale, e.g., `de` or `sv-SE`, or the default rules if it is `None`, ignoring case if `ignore_case` is true.
+    ///
+    /// Gives an [`Error::Config`] if the locale is not a valid language tag.
+    pub fn unicode(locale: Option<&str>, ignore_case: bool) -> Result<Self> {
+        let data_locale = locale
+            .unwrap_or("und")
+            .parse()
+            .map_err(|e| Error::Config(format!("invalid locale '{}': {}", locale.unwrap_or_default(), e)))?;
+        let mut options = CollatorOptions::new();
+        // The secondary strength tells apart accents, but not case
+        options.strength = Some(if ignore_case { Strength::Secondary } else { Strength::Tertiary });
+        let collator = Collator::try_new(&data_locale, options)
+            .map_err(|e| Error::Config(format!("no collation for locale '{}': {}", locale.unwrap_or_default(), e)))?;
+        Ok(Self {
+            collator: Some(Arc::new(collator)),
+            ignore_case,
+        })
+    }
+
+    /// Creates a `Collation` of the given kind.
+    pub fn new(kind: CollationKind, locale: Option<&str>, ignore_case: bool) -> Result<Self> {
+        match kind {
+            CollationKind::Bytes => Ok(Self::bytes(ignore_case)),
+            CollationKind::Unicode => Self::unicode(locale, ignore_case),
+        }
+    }
+
+    /// Compares two words, and their bytes if the collation considers them equal.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let order = match &self.collator {
+            Some(collator) => collator.compare(a, b),
+            None if self.ignore_case => {
+                a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase))
+            }
+            None => Ordering::Equal,
+        };
+        order.then_with(|| a.cmp(b))
+    }
+
+    /// Compares two counts like [`Count`]'s `Ord` implementation does, i.e., the highest count first, but with the
+    /// words of the same count compared with this collation.
+    pub fn compare_counts(&self, a: &Count<'_>, b: &Count<'_>) -> Ordering {
+        b.count.cmp(&a.count).then_with(|| self.compare(a.word, b.word))
+    }
+}
+
+impl Debug for Collation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Collation")
+            .field("unicode", &self.collator.is_some())
+            .field("ignore_case", &self.ignore_case)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted<'a>(collation: &Collation, words: &[&'a str]) -> Vec<&'a str> {
+        let mut words = words.to_vec();
+        words.sort_by(|a, b| collation.compare(a, b));
+        words
+    }
+
+    const WORDS: [&str; 6] = ["zebra", "Ähnlich", "ahnung", "apple", "Apple", "éclair"];
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(sorted(&Collation::default(), &WORDS), ["Apple", "ahnung", "apple", "zebra", "Ähnlich", "éclair"]);
+        let ignore_case = sorted(&Collation::bytes(true), &WORDS);
+        assert_eq!(ignore_case, ["ahnung", "Apple", "apple", "zebra", "Ähnlich", "éclair"]);
+    }
+
+    #[test]
+    fn test_send_and_sync() {
+        fn assert_send_and_sync<T: Send + Sync>(_: &T) {}
+        assert_send_and_sync(&Collation::unicode(Some("de"), true).unwrap());
+    }
+
+    #[test]
+    fn test_unicode() {
+        let root = Collation::unicode(None, false).unwrap();
+        assert_eq!(sorted(&root, &WORDS), ["Ähnlich", "ahnung", "apple", "Apple", "éclair", "zebra"]);
+        let swedish = Collation::unicode(Some("sv"), false).unwrap();
+        assert_eq!(sorted(&swedish, &WORDS), ["ahnung", "apple", "Apple", "éclair", "zebra", "Ähnlich"]);
+        // Ignoring case makes "Apple" and "apple" equal, so they are ordered by their bytes
+        let ignore_case = Collation::unicode(None, true).unwrap();
+        assert_eq!(sorted(&ignore_case, &["apple", "Apple"]), ["Apple", "apple"]);
+        assert!(matches!(Collation::unicode(Some("not a locale!"), false), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_compare_counts() {
+        let collation = Collation::unicode(Some("de"), false).unwrap();
+        let mut counts = [Count::new("zebra", 1), Count::new("ähnlich", 1), Count::new("the", 2)];
+        counts.sort_by(|a, b| collation.compare_counts(a, b));
+        let words: Vec<&str> = counts.iter().map(|count| count.word).collect();
+        assert_eq!(words, ["the", "ähnlich", "zebra"]);
+    }
+}
@@ -0,0 +1,220 @@
+//!
+//! Contains code to count which words occur near each other in a text, and to rank the pairs of words, or
+//! collocations, by how much more often they occur together than would be expected by chance.
+//!
+//! A pair `(first, second)` is counted each time `second` occurs at most `window` words after `first`, so a window of
+//! 1 counts adjacent words only. Stop words are skipped, so they neither form pairs nor take up room in the window.
+//!
+//! The scores follow the usual definitions for a 2×2 contingency table of the two words, where the pair count is
+//! divided by the window size so that it is comparable to the word counts:
+//!
+//! - PMI (pointwise mutual information), `log2(observed / expected)`, which favors rare pairs that always occur
+//!   together
+//! - t-score, `(observed - expected) / sqrt(observed)`, which favors frequent pairs
+//! - log-likelihood ratio, Dunning's G², which balances the two
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::collocations::Measure;
+//! use word_frequency::frequency::FrequencyCounter;
+//!
+//! let mut frequency_counter = FrequencyCounter::new("new york is big. i like new york. york is old, new is good.");
+//! let cooccurrences = frequency_counter.count_cooccurrences(1);
+//! assert_eq!(cooccurrences.count("new", "york"), 2);
+//! assert_eq!(cooccurrences.count("york", "new"), 0);
+//! let collocations = cooccurrences.collocations(Measure::LogLikelihood, None, 2);
+//! assert_eq!(collocations.len(), 2);
+//! assert_eq!((collocations[0].first.as_str(), collocations[0].second.as_str()), ("new", "york"));
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+
+use clap::ValueEnum;
+
+/// The measures that collocations can be ranked by.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, ValueEnum)]
+pub enum Measure {
+    /// Pointwise mutual information
+    Pmi,
+    /// Student's t-score
+    TScore,
+    /// Dunning's log-likelihood ratio
+    LogLikelihood,
+}
+
+/// Counts of words and of ordered pairs of words that occur within a window of each other.
+#[derive(Debug, Clone)]
+pub struct Cooccurrences {
+    window: usize,
+    word_counts: HashMap<String, usize>,
+    pair_counts: HashMap<(String, String), usize>,
+    total_words: usize,
+    /// The last `window` words added, which form pairs with the next word
+    recent_words: VecDeque<String>,
+}
+
+/// A pair of words and its score for the measure it was ranked by.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Collocation {
+    pub first: String,
+    pub second: String,
+    /// The number of times `second` occurred within the window after `first`
+    pub count: usize,
+    pub score: f64,
+}
+
+impl Cooccurrences {
+    /// Creates empty counts for pairs of words at most `window` words apart. A window of 0 is treated as 1.
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            word_counts: HashMap::new(),
+            pair_counts: HashMap::new(),
+            total_words: 0,
+            recent_words: VecDeque::new(),
+        }
+    }
+
+    /// Adds the next word of the text, pairing it with the words in the window before it.
+    pub fn add_word(&mut self, word: &str) {
+        for previous in &self.recent_words {
+            *self.pair_counts.entry((previous.clone(), word.to_string())).or_insert(0) += 1;
+        }
+        if self.recent_words.len() == self.window {
+            self.recent_words.pop_front();
+        }
+        self.recent_words.push_back(word.to_string());
+        *self.word_counts.entry(word.to_string()).or_insert(0) += 1;
+        self.total_words += 1;
+    }
+
+    /// Gives the number of times `second` occurred within the window after `first`.
+    pub fn count(&self, first: &str, second: &str) -> usize {
+        self.pair_counts.get(&(first.to_string(), second.to_string())).copied().unwrap_or(0)
+    }
+
+    /// Gives the number of occurrences of the given word.
+    pub fn word_count(&self, word: &str) -> usize {
+        self.word_counts.get(word).copied().unwrap_or(0)
+    }
+
+    /// Gives the total number of words counted.
+    pub fn total_words(&self) -> usize {
+        self.total_words
+    }
+
+    /// Gives the pairs that occurred at least `min_count` times, sorted by descending score for the given measure,
+    /// and then by the words. If a target word is given, only pairs containing it are included. The target is
+    /// matched exactly, so it should be in lower case if the input was converted to lower case.
+    pub fn collocations(&self, measure: Measure, target: Option<&str>, min_count: usize) -> Vec<Collocation> {
+        let mut collocations: Vec<Collocation> = self
+            .pair_counts
+            .iter()
+            .filter(|(_, count)| **count >= min_count)
+            .filter(|((first, second), _)| target.is_none_or(|target| first == target || second == target))
+            .map(|((first, second), count)| Collocation {
+                first: first.clone(),
+                second: second.clone(),
+                count: *count,
+                score: self.score(measure, first, second, *count),
+            })
+            .collect();
+        collocations.sort_by(|a, b| {
+            b.score.total_cmp(&a.score).then_with(|| a.first.cmp(&b.first)).then_with(|| a.second.cmp(&b.second))
+        });
+        collocations
+    }
+
+    fn score(&self, measure: Measure, first: &str, second: &str, count: usize) -> f64 {
+        let n = self.total_words as f64;
+        let first_count = self.word_count(first) as f64;
+        let second_count = self.word_count(second) as f64;
+        let observed = count as f64 / self.window as f64;
+        let expected = first_count * second_count / n;
+        match measure {
+            Measure::Pmi => (observed / expected).log2(),
+            Measure::TScore => (observed - expected) / observed.sqrt(),
+            Measure::LogLikelihood => {
+                let observed_table = [
+                    observed,
+                    first_count - observed,
+                    second_count - observed,
+                    (n - first_count - second_count + observed).max(0.0),
+                ];
+                let expected_table = [
+                    expected,
+                    first_count * (n - second_count) / n,
+                    (n - first_count) * second_count / n,
+                    (n - first_count) * (n - second_count) / n,
+                ];
+                2.0 * observed_table
+                    .iter()
+                    .zip(expected_table)
+                    .filter(|(o, e)| **o > 0.0 && *e > 0.0)
+                    .map(|(o, e)| o * (o / e).ln())
+                    .sum::<f64>()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frequency::FrequencyCounter;
+
+    #[test]
+    fn test_count_window() {
+        let mut cooccurrences = Cooccurrences::new(2);
+        for word in ["a", "b", "c", "a", "b"] {
+            cooccurrences.add_word(word);
+        }
+        assert_eq!(cooccurrences.count("a", "b"), 2);
+        assert_eq!(cooccurrences.count("a", "c"), 1);
+        assert_eq!(cooccurrences.count("b", "a"), 1);
+        assert_eq!(cooccurrences.count("b", "b"), 0);
+        assert_eq!(cooccurrences.count("c", "b"), 1);
+        assert_eq!(cooccurrences.word_count("a"), 2);
+        assert_eq!(cooccurrences.total_words(), 5);
+    }
+
+    #[test]
+    fn test_stop_words_are_skipped() {
+        let mut frequency_counter = FrequencyCounter::new("the cat and the hat");
+        frequency_counter.set_stop_words(["the", "and"].iter().map(|w| w.to_string()).collect());
+        let cooccurrences = frequency_counter.count_cooccurrences(1);
+        assert_eq!(cooccurrences.count("cat", "hat"), 1);
+        assert_eq!(cooccurrences.total_words(), 2);
+    }
+
+    #[test]
+    fn test_scores() {
+        // "x y" always occurs together, while "a" and "b" occur together no more often than by chance
+        let mut cooccurrences = Cooccurrences::new(1);
+        for word in "x y a b a a b b x y a b b a x y".split(' ') {
+            cooccurrences.add_word(word);
+        }
+        let pmi = cooccurrences.collocations(Measure::Pmi, None, 2);
+        assert_eq!(pmi[0].first, "x");
+        assert!((pmi[0].score - (16.0f64 / 3.0).log2()).abs() < 1e-9);
+        let t_score = cooccurrences.collocations(Measure::TScore, None, 1);
+        assert_eq!((t_score[0].first.as_str(), t_score[0].second.as_str()), ("x", "y"));
+        let log_likelihood = cooccurrences.collocations(Measure::LogLikelihood, None, 1);
+        assert_eq!((log_likelihood[0].first.as_str(), log_likelihood[0].second.as_str()), ("x", "y"));
+        assert!(log_likelihood.iter().all(|c| c.score >= 0.0));
+    }
+
+    #[test]
+    fn test_target_word() {
+        let mut cooccurrences = Cooccurrences::new(1);
+        for word in "a b c a b d".split(' ') {
+            cooccurrences.add_word(word);
+        }
+        let collocations = cooccurrences.collocations(Measure::Pmi, Some("b"), 1);
+        let pairs: Vec<(&str, &str)> =
+            collocations.iter().map(|c| (c.first.as_str(), c.second.as_str())).collect();
+        assert_eq!(pairs.len(), 3);
+        assert!(pairs.contains(&("a", "b")) && pairs.contains(&("b", "c")) && pairs.contains(&("b", "d")));
+    }
+}
@@ -0,0 +1,154 @@
+//!
+//! Contains code to transparently decompress input that has been compressed with gzip, zstd or bzip2.
+//!
+//! The compression format is detected by looking at the "magic bytes" at the start of the input, so no file
+//! name extension is needed, and the same detection works when reading from stdin.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::{Read, Write};
+//! use flate2::{Compression as Level, write::GzEncoder};
+//! use word_frequency::compression;
+//!
+//! let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+//! encoder.write_all(b"Hello, World!").unwrap();
+//! let compressed = encoder.finish().unwrap();
+//!
+//! let mut reader = compression::decompress(Box::new(&compressed[..])).unwrap();
+//! let mut text = String::new();
+//! reader.read_to_string(&mut text).unwrap();
+//! assert_eq!(text, "Hello, World!");
+//! ```
+
+use std::io::{Cursor, Read};
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+
+use crate::error::{Error, Result};
+
+/// The magic bytes that start a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The magic bytes that start a zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// The magic bytes that start a bzip2 stream, followed by a block size digit between '1' and '9'.
+const BZIP2_MAGIC: [u8; 3] = *b"BZh";
+/// The number of bytes we need to look at to recognize any of the supported formats.
+const HEADER_LENGTH: u64 = 4;
+
+/// The compression formats that we can recognize.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Determines the compression format from the first bytes of the input.
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(&GZIP_MAGIC) {
+            Compression::Gzip
+        } else if header.starts_with(&ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if header.starts_with(&BZIP2_MAGIC) && matches!(header.get(3), Some(b'1'..=b'9')) {
+            Compression::Bzip2
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Wraps the given reader in a decoder matching the compression format of the input, or returns a reader for the
+/// unchanged input if it is not compressed.
+///
+/// The input is decompressed as it is read, so the whole compressed input never needs to be kept in memory. Errors
+/// in the compressed data are reported when reading from the returned reader.
+pub fn decompress<'a>(mut reader: Box<dyn Read + 'a>) -> Result<Box<dyn Read + 'a>> {
+    let mut header = Vec::new();
+    reader.by_ref().take(HEADER_LENGTH).read_to_end(&mut header).map_err(Error::reading)?;
+    let compression = Compression::detect(&header);
+    let reader = Cursor::new(header).chain(reader);
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        #[cfg(not(target_arch = "wasm32"))]
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        #[cfg(target_arch = "wasm32")]
+        Compression::Zstd => {
+            return Err(Error::Decode {
+                path: None,
+                message: "zstd input cannot be decompressed in WebAssembly".to_string(),
+            });
+        }
+        Compression::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const TEXT: &str = "I'm nobody! Who are you?\nAre you nobody, too?\n";
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08, 0x00]), Compression::Gzip);
+        assert_eq!(Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd]), Compression::Zstd);
+        assert_eq!(Compression::detect(b"BZh9"), Compression::Bzip2);
+        assert_eq!(Compression::detect(b"BZh!"), Compression::None);
+        assert_eq!(Compression::detect(b"BZh"), Compression::None);
+        assert_eq!(Compression::detect(b"Hello"), Compression::None);
+        assert_eq!(Compression::detect(b""), Compression::None);
+    }
+
+    #[test]
+    fn test_decompress_uncompressed() {
+        assert_eq!(decompress_to_string(TEXT.as_bytes()), TEXT);
+        assert_eq!(decompress_to_string(b"a"), "a");
+        assert_eq!(decompress_to_string(b""), "");
+    }
+
+    #[test]
+    fn test_decompress_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(TEXT.as_bytes()).unwrap();
+        let mut compressed = encoder.finish().unwrap();
+        assert_eq!(decompress_to_string(&compressed), TEXT);
+
+        // A file made by concatenating gzip files should give the concatenated contents, like `zcat` does
+        compressed.extend_from_slice(&compressed.clone());
+        assert_eq!(decompress_to_string(&compressed), TEXT.repeat(2));
+    }
+
+    #[test]
+    fn test_decompress_zstd() {
+        let compressed = zstd::stream::encode_all(TEXT.as_bytes(), 0).unwrap();
+        assert_eq!(decompress_to_string(&compressed), TEXT);
+    }
+
+    #[test]
+    fn test_decompress_bzip2() {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(TEXT.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(decompress_to_string(&compressed), TEXT);
+    }
+
+    #[test]
+    fn test_decompress_corrupt_input() {
+        let mut reader = decompress(Box::new(&[0x1f, 0x8b, 0x00, 0x00, 0x00][..])).unwrap();
+        assert!(reader.read_to_string(&mut String::new()).is_err());
+    }
+
+    fn decompress_to_string(input: &[u8]) -> String {
+        let mut reader = decompress(Box::new(input)).unwrap();
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        output
+    }
+}
@@ -0,0 +1,385 @@
+//!
+//! Contains code to read a TOML configuration file with settings for the command line options, so that the options
+//! that are the same in every run do not need to be given every time.
+//!
+//! Each setting is named like the long form of an option, e.g., `num-words` for `--num-words`, with either dashes or
+//! underscores. Flags are set with booleans, and other options with strings or numbers. The settings become the
+//! defaults of the options, so options given on the command line override them. This includes the settings for
+//! options that conflict with an option on the command line, e.g., `no-stop-words = true` is ignored when
+//! `--stop-words` is given, see [`Config::without_conflicts()`].
+//!
+//! Settings may be grouped in the tables `[input]`, `[tokenizer]`, `[filter]` and `[output]`, which is only for
+//! readability, e.g., with `ngrams` under `[tokenizer]` and `stop-words` under `[filter]`. A table named like a
+//! subcommand, e.g., `[collocations]`, gives the settings for the options of that subcommand.
+//!
+//! The file is looked for as `word-frequency.toml` in the current directory, and then as
+//! `word-frequency/config.toml` in the XDG config directory, `$XDG_CONFIG_HOME` or `~/.config`.
+//!
+//! # Examples
+//!
+//! ```
+//! use clap::{Arg, ArgAction, Command};
+//! use word_frequency::config::Config;
+//!
+//! let config = Config::parse(
+//!     "num-words = 10\n[filter]\nno_stop_words = true\n",
+//!     "word-frequency.toml",
+//! )
+//! .unwrap();
+//! let command = Command::new("word-frequency")
+//!     .arg(Arg::new("num_words").long("num-words").default_value("25"))
+//!     .arg(Arg::new("no_stop_words").long("no-stop-words").action(ArgAction::SetTrue))
+//!     .arg(Arg::new("scale").long("scale").default_value("absolute"));
+//! let command = config.apply(command).unwrap();
+//! let matches = command.clone().get_matches_from(["word-frequency", "--scale", "percent"]);
+//! assert_eq!(matches.get_one::<String>("num_words").unwrap(), "10");
+//! assert!(matches.get_flag("no_stop_words"));
+//! let settings = word_frequency::config::effective_settings(&command, &matches, Some(&config));
+//! assert!(settings.contains("num-words = 10  # word-frequency.toml\n"));
+//! assert!(settings.contains("scale = \"percent\"  # command line\n"));
+//! ```
+
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command};
+use toml::{Table, Value};
+
+use crate::error::{Error, Result};
+
+/// The name of the configuration file in the current directory.
+pub const FILE_NAME: &str = "word-frequency.toml";
+/// The tables that only group settings, as opposed to the tables for subcommands.
+const GROUPS: [&str; 4] = ["input", "tokenizer", "filter", "output"];
+
+/// The settings read from a configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    path: PathBuf,
+    settings: Vec<Setting>,
+}
+
+/// A setting for an option of the command, or of a subcommand.
+#[derive(Debug, Clone, PartialEq)]
+struct Setting {
+    subcommand: Option<String>,
+    /// The long name of the option, with dashes
+    name: String,
+    value: String,
+}
+
+impl Config {
+    /// Reads the configuration file with the given path.
+    ///
+    /// Gives an [`Error::Io`] if the file cannot be read, and an [`Error::Config`] if it is not valid TOML or a
+    /// setting has a value that cannot be an option value, like an array.
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        Self::parse(&text, path)
+    }
+
+    /// Parses the contents of a configuration file, where the path is only used in messages.
+    pub fn parse(text: &str, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let table: Table = text
+            .parse()
+            .map_err(|e| Error::Config(format!("{} is not valid TOML: {}", path.display(), e)))?;
+        let mut settings = Vec::new();
+        for (key, value) in table {
+            match value {
+                Value::Table(table) => {
+                    let subcommand = if GROUPS.contains(&key.as_str()) { None } else { Some(key) };
+                    for (key, value) in table {
+                        settings.push(Setting::new(subcommand.clone(), &key, value, &path)?);
+                    }
+                }
+                value => settings.push(Setting::new(None, &key, value, &path)?),
+            }
+        }
+        Ok(Self { path, settings })
+    }
+
+    /// Gives the path that the configuration was read from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gives the value of the setting for the option with the given long name in the given subcommand, or in the main
+    /// command if it is `None`.
+    pub fn get(&self, subcommand: Option<&str>, name: &str) -> Option<&str> {
+        self.settings
+            .iter()
+            .find(|setting| setting.subcommand.as_deref() == subcommand && setting.name == name)
+            .map(|setting| setting.value.as_str())
+    }
+
+    /// Makes the settings the default values of the matching options of the given command and its subcommands.
+    ///
+    /// Gives an [`Error::Config`] if there is no option or subcommand matching a setting.
+    pub fn apply(&self, mut command: Command) -> Result<Command> {
+        for setting in &self.settings {
+            let target = match &setting.subcommand {
+                None => &command,
+                Some(name) => command.find_subcommand(name).ok_or_else(|| {
+                    Error::Config(format!("unknown table [{}] in {}", name, self.path.display()))
+                })?,
+            };
+            if !target.get_arguments().any(|arg| arg.get_long() == Some(&setting.name)) {
+                return Err(Error::Config(format!("unknown setting '{}' in {}", setting.name, self.path.display())));
+            }
+        }
+        // Change the arguments in place, since replacing them one by one would change their order in the help
+        command = command.mut_args(|arg| self.set_default(None, arg));
+        let mut subcommands: Vec<&str> = self.settings.iter().filter_map(|s| s.subcommand.as_deref()).collect();
+        subcommands.dedup();
+        for name in subcommands {
+            command = command.mut_subcommand(name, |subcommand| {
+                subcommand.mut_args(|arg| self.set_default(Some(name), arg))
+            });
+        }
+        Ok(command)
+    }
+
+    /// Gives the settings without the ones for options that conflict with an option given on the command line. Clap
+    /// only checks the conflicts of the options on the command line, not of their defaults, so the command should be
+    /// parsed again with the remaining settings, see the [module documentation](self).
+    ///
+    /// The command and the matches must be the ones that the settings were applied to and parsed with.
+    pub fn without_conflicts(&self, command: &Command, matches: &ArgMatches) -> Self {
+        let settings = self
+            .settings
+            .iter()
+            .filter(|setting| {
+                let (command, matches) = match &setting.subcommand {
+                    None => (Some(command), Some(matches)),
+                    Some(name) => (command.find_subcommand(name), matches.subcommand_matches(name)),
+                };
+                command.zip(matches).is_none_or(|(command, matches)| !conflicts(command, matches, &setting.name))
+            })
+            .cloned()
+            .collect();
+        Self {
+            path: self.path.clone(),
+            settings,
+        }
+    }
+
+    fn set_default(&self, subcommand: Option<&str>, arg: Arg) -> Arg {
+        match arg.get_long().and_then(|name| self.get(subcommand, name)) {
+            Some(value) => arg.default_value(value.to_string()),
+            None => arg,
+        }
+    }
+}
+
+/// Tells if the option with the given long name conflicts with an option given on the command line, in either
+/// direction, since clap checks conflicts both ways.
+fn conflicts(command: &Command, matches: &ArgMatches, name: &str) -> bool {
+    let Some(arg) = command.get_arguments().find(|arg| arg.get_long() == Some(name)) else {
+        return false;
+    };
+    let conflicts_with = |a: &Arg, b: &Arg| command.get_arg_conflicts_with(a).iter().any(|c| c.get_id() == b.get_id());
+    command
+        .get_arguments()
+        .filter(|other| matches.value_source(other.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .any(|other| conflicts_with(arg, other) || conflicts_with(other, arg))
+}
+
+impl Setting {
+    fn new(subcommand: Option<String>, key: &str, value: Value, path: &Path) -> Result<Self> {
+        let value = match value {
+            Value::String(s) => s,
+            Value::Integer(i) => i.to_string(),
+            Value::Float(f) => f.to_string(),
+            Value::Boolean(b) => b.to_string(),
+            _ => {
+                return Err(Error::Config(format!(
+                    "the setting '{}' in {} must be a string, a number or a boolean",
+                    key,
+                    path.display()
+                )));
+            }
+        };
+        Ok(Self {
+            subcommand,
+            name: key.replace('_', "-"),
+            value,
+        })
+    }
+}
+
+/// Gives the path of the configuration file to use, if there is one, see the [module documentation](self).
+pub fn find() -> Option<PathBuf> {
+    search_paths(std::env::var_os("XDG_CONFIG_HOME"), std::env::var_os("HOME")).into_iter().find(|path| path.is_file())
+}
+
+/// Gives the paths where the configuration file is looked for, in order.
+fn search_paths(xdg_config_home: Option<OsString>, home: Option<OsString>) -> Vec<PathBuf> {
+    let config_dir = xdg_config_home
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| PathBuf::from(home).join(".config")));
+    let mut paths = vec![PathBuf::from(FILE_NAME)];
+    paths.extend(config_dir.map(|dir| dir.join("word-frequency").join("config.toml")));
+    paths
+}
+
+/// Gives the effective settings of a run as a configuration file, with a comment after each setting telling where its
+/// value came from. Options without a value are commented out. The settings for subcommands that are not run are
+/// their default values.
+pub fn effective_settings(command: &Command, matches: &ArgMatches, config: Option<&Config>) -> String {
+    let mut settings = String::new();
+    write_settings(&mut settings, command, None, Some(matches), config);
+    for subcommand in command.get_subcommands() {
+        let name = subcommand.get_name();
+        let matches = matches.subcommand_matches(name);
+        writeln!(settings, "\n[{}]", name).unwrap();
+        write_settings(&mut settings, subcommand, Some(name), matches, config);
+    }
+    settings
+}
+
+fn write_settings(
+    settings: &mut String,
+    command: &Command,
+    subcommand: Option<&str>,
+    matches: Option<&ArgMatches>,
+    config: Option<&Config>,
+) {
+    for arg in command.get_arguments() {
+        let Some(name) = arg.get_long() else {
+            continue;
+        };
+        if ["help", "version", "print-config"].contains(&name) {
+            continue;
+        }
+        let id = arg.get_id().as_str();
+        let value = match matches {
+            Some(matches) => matches.get_raw(id).and_then(|mut values| values.next_back()),
+            None => arg.get_default_values().last().map(|value| value.as_os_str()),
+        };
+        let Some(value) = value else {
+            writeln!(settings, "# {} =", name).unwrap();
+            continue;
+        };
+        let from_config = config.is_some_and(|config| config.get(subcommand, name).is_some());
+        let source = match matches.and_then(|matches| matches.value_source(id)) {
+            Some(ValueSource::CommandLine) => "command line".to_string(),
+            Some(ValueSource::EnvVariable) => "environment".to_string(),
+            _ if from_config => config.unwrap().path.display().to_string(),
+            _ => "default".to_string(),
+        };
+        writeln!(settings, "{} = {}  # {}", name, toml_value(&value.to_string_lossy()), source).unwrap();
+    }
+}
+
+/// Formats an option value as a TOML value, without quotes if it is a boolean or a number.
+fn toml_value(value: &str) -> String {
+    let is_number = value.parse::<i64>().is_ok() || value.parse::<f64>().is_ok_and(|f| f.is_finite());
+    if value == "true" || value == "false" || is_number {
+        value.to_string()
+    } else {
+        Value::String(value.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ArgAction;
+
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("word-frequency")
+            .arg(Arg::new("num_words").long("num-words").default_value("25"))
+            .arg(Arg::new("profile").long("profile").action(ArgAction::SetTrue))
+            .arg(Arg::new("svg").long("svg"))
+            .arg(Arg::new("stop_words").long("stop-words").default_value("stop-words.txt"))
+            .arg(
+                Arg::new("no_stop_words").long("no-stop-words").action(ArgAction::SetTrue).conflicts_with("stop_words"),
+            )
+            .arg(Arg::new("file_name"))
+            .subcommand(Command::new("collocations").arg(Arg::new("window").long("window").default_value("2")))
+    }
+
+    #[test]
+    fn test_parse_groups_and_subcommands() {
+        let config = Config::parse(
+            "num_words = 5\n[output]\nprofile = true\nsvg = \"a.svg\"\n[collocations]\nwindow = 3\n",
+            "a.toml",
+        )
+        .unwrap();
+        assert_eq!(config.get(None, "num-words"), Some("5"));
+        assert_eq!(config.get(None, "profile"), Some("true"));
+        assert_eq!(config.get(None, "svg"), Some("a.svg"));
+        assert_eq!(config.get(Some("collocations"), "window"), Some("3"));
+        assert_eq!(config.get(None, "window"), None);
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let config = Config::parse("num-words = 5\nprofile = true\n[collocations]\nwindow = 3\n", "a.toml").unwrap();
+        let command = config.apply(command()).unwrap();
+        let matches = command.clone().try_get_matches_from(["wf", "--num-words", "7", "x.txt"]).unwrap();
+        assert_eq!(matches.get_one::<String>("num_words").unwrap(), "7");
+        assert!(matches.get_flag("profile"));
+        let matches = command.clone().try_get_matches_from(["wf", "collocations"]).unwrap();
+        let (_, collocations) = matches.subcommand().unwrap();
+        assert_eq!(collocations.get_one::<String>("window").unwrap(), "3");
+    }
+
+    #[test]
+    fn test_command_line_overrides_conflicting_config() {
+        let config = Config::parse("no-stop-words = true\nnum-words = 5\n", "a.toml").unwrap();
+        let args = ["wf", "--stop-words", "s.txt", "x.txt"];
+        let matches = config.apply(command()).unwrap().try_get_matches_from(args).unwrap();
+        // Clap does not check the conflicts of defaults
+        assert!(matches.get_flag("no_stop_words"));
+        let config = config.without_conflicts(&config.apply(command()).unwrap(), &matches);
+        assert_eq!(config.get(None, "no-stop-words"), None);
+        assert_eq!(config.get(None, "num-words"), Some("5"));
+        let matches = config.apply(command()).unwrap().try_get_matches_from(args).unwrap();
+        assert!(!matches.get_flag("no_stop_words"));
+        assert_eq!(matches.get_one::<String>("stop_words").unwrap(), "s.txt");
+        // The reverse conflict, and a setting that conflicts with nothing on the command line
+        let config = Config::parse("stop-words = \"a.txt\"\n", "a.toml").unwrap();
+        let matches = config.apply(command()).unwrap().try_get_matches_from(["wf", "--no-stop-words"]).unwrap();
+        assert_eq!(config.without_conflicts(&command(), &matches).get(None, "stop-words"), None);
+        let matches = config.apply(command()).unwrap().try_get_matches_from(["wf", "x.txt"]).unwrap();
+        assert_eq!(config.without_conflicts(&command(), &matches), config);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let error = Config::parse("no-such-option = 1", "a.toml").unwrap().apply(command()).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid configuration: unknown setting 'no-such-option' in a.toml");
+        let error = Config::parse("[serve]\nwindow = 1", "a.toml").unwrap().apply(command()).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid configuration: unknown table [serve] in a.toml");
+        assert!(matches!(Config::parse("svg = [1, 2]", "a.toml"), Err(Error::Config(_))));
+        assert!(matches!(Config::parse("svg = ", "a.toml"), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_effective_settings() {
+        let config = Config::parse("profile = true", "a.toml").unwrap();
+        let command = config.apply(command()).unwrap();
+        let matches = command.clone().try_get_matches_from(["wf", "--svg", "my \"chart\".svg", "x.txt"]).unwrap();
+        assert_eq!(
+            effective_settings(&command, &matches, Some(&config)),
+            "num-words = 25  # default\nprofile = true  # a.toml\nsvg = 'my \"chart\".svg'  # command line\n\
+             stop-words = \"stop-words.txt\"  # default\nno-stop-words = false  # default\n\
+             \n[collocations]\nwindow = 2  # default\n"
+        );
+    }
+
+    #[test]
+    fn test_search_paths() {
+        let paths = search_paths(Some("/xdg".into()), Some("/home/me".into()));
+        assert_eq!(paths, [PathBuf::from(FILE_NAME), PathBuf::from("/xdg/word-frequency/config.toml")]);
+        let paths = search_paths(Some("".into()), Some("/home/me".into()));
+        assert_eq!(paths[1], PathBuf::from("/home/me/.config/word-frequency/config.toml"));
+        assert_eq!(search_paths(None, None), [PathBuf::from(FILE_NAME)]);
+    }
+}
@@ -0,0 +1,136 @@
+//!
+//! Contains the error type used by the library.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::error::Error;
+//! use word_frequency::frequency::FrequencyCounter;
+//!
+//! let error = FrequencyCounter::from_file("no-such-file.txt").unwrap_err();
+//! assert!(matches!(error, Error::Io { .. }));
+//! assert!(error.to_string().starts_with("Error reading 'no-such-file.txt'"));
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// The errors that can occur when reading and counting words.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be opened or read. The path is `None` when reading from a reader without a known file, e.g.,
+    /// stdin.
+    Io { path: Option<PathBuf>, source: std::io::Error },
+    /// The input could be read, but it is not valid text in the expected format, e.g., it is not UTF-8, the
+    /// compressed data is corrupt, or a CSV or JSON record is malformed.
+    Decode { path: Option<PathBuf>, message: String },
+    /// An entry in a stop words file can never match a word, e.g., because it contains spaces or digits.
+    StopWords { path: PathBuf, line: usize, message: String },
+    /// The options given are invalid or do not match the input, e.g., a CSV column that does not exist.
+    Config(String),
+}
+
+/// A `Result` with the error type of this library.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Creates an error for an I/O error that occurred while opening or reading the given file.
+    pub fn io<P: AsRef<Path>>(path: P, source: std::io::Error) -> Self {
+        Error::Io {
+            path: Some(path.as_ref().to_path_buf()),
+            source,
+        }
+    }
+
+    /// Classifies an I/O error that occurred while reading, and possibly decompressing, the input: errors caused by
+    /// invalid or truncated data give a [`Error::Decode`], any other error gives an [`Error::Io`].
+    pub fn reading(source: std::io::Error) -> Self {
+        match source.kind() {
+            ErrorKind::InvalidData | ErrorKind::InvalidInput | ErrorKind::UnexpectedEof => Error::Decode {
+                path: None,
+                message: source.to_string(),
+            },
+            _ => Error::Io { path: None, source },
+        }
+    }
+
+    /// Sets the path of the file that the error occurred in, unless the error already has a path.
+    pub fn with_path<P: AsRef<Path>>(mut self, file_path: P) -> Self {
+        match &mut self {
+            Error::Io { path, .. } | Error::Decode { path, .. } if path.is_none() => {
+                *path = Some(file_path.as_ref().to_path_buf());
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Gives the path of the file that the error occurred in, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::Io { path, .. } | Error::Decode { path, .. } => path.as_deref(),
+            Error::StopWords { path, .. } => Some(path),
+            Error::Config(_) => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io { path: Some(path), source } => write!(f, "Error reading '{}': {}", path.display(), source),
+            Error::Io { path: None, source } => write!(f, "Error reading input: {}", source),
+            Error::Decode { path: Some(path), message } => {
+                write!(f, "Error decoding '{}': {}", path.display(), message)
+            }
+            Error::Decode { path: None, message } => write!(f, "Error decoding input: {}", message),
+            Error::StopWords { path, line, message } => {
+                write!(f, "Error in stop words file '{}', line {}: {}", path.display(), line, message)
+            }
+            Error::Config(message) => write!(f, "Invalid configuration: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        Error::Io { path: None, source }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reading_classifies_errors() {
+        let error = Error::reading(std::io::Error::new(ErrorKind::InvalidData, "stream did not contain valid UTF-8"));
+        assert!(matches!(error, Error::Decode { path: None, .. }));
+        let error = Error::reading(std::io::Error::new(ErrorKind::UnexpectedEof, "truncated"));
+        assert!(matches!(error, Error::Decode { path: None, .. }));
+        let error = Error::reading(std::io::Error::new(ErrorKind::PermissionDenied, "denied"));
+        assert!(matches!(error, Error::Io { path: None, .. }));
+    }
+
+    #[test]
+    fn test_with_path() {
+        let error = Error::reading(std::io::Error::new(ErrorKind::InvalidData, "bad")).with_path("a.txt");
+        assert_eq!(error.path(), Some(Path::new("a.txt")));
+        assert_eq!(error.to_string(), "Error decoding 'a.txt': bad");
+        let error = error.with_path("b.txt");
+        assert_eq!(error.path(), Some(Path::new("a.txt")));
+        let error = Error::Config("no column".to_string()).with_path("a.txt");
+        assert_eq!(error.path(), None);
+        assert_eq!(error.to_string(), "Invalid configuration: no column");
+    }
+}
@@ -0,0 +1,385 @@
+//!
+//! Contains code to export counts to a table in a SQLite database or a Parquet file, so that the counts of many runs
+//! can be queried with SQL. SQLite needs the `sqlite` feature, and Parquet the `parquet` feature.
+//!
+//! Each export appends one row per word to the table, which is created if it does not exist, with the columns:
+//!
+//! - `word`: the word or n-gram
+//! - `count`: the number of occurrences
+//! - `share`: the count as a fraction of all counted words, between 0 and 1
+//! - `relative`: the count as a fraction of the count of the most common word, between 0 and 1
+//! - `source`: the name of the file that was counted
+//! - `run_timestamp`: when the words were counted, in UTC
+//!
+//! In SQLite, the table is named `word_frequencies`, and the timestamp is stored as text in RFC 3339 format, which
+//! the SQLite date and time functions understand. Parquet files cannot be appended to, so the rows of earlier runs
+//! are read and written again to a new file, with one row group per run, that then replaces the old file.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::SystemTime;
+//! use word_frequency::export::{self, ExportFormat};
+//! use word_frequency::frequency::FrequencyCounter;
+//!
+//! let mut frequency_counter = FrequencyCounter::from_file("poem.txt").unwrap();
+//! let word_frequencies = frequency_counter.count_frequencies();
+//! let sorted_frequencies = FrequencyCounter::sort_frequencies(&word_frequencies);
+//! let format = ExportFormat::from_path("counts.db").unwrap();
+//! let timestamp = SystemTime::now().into();
+//! export::export("counts.db", format, "poem.txt", timestamp, &sorted_frequencies).unwrap();
+//! ```
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+
+use crate::error::{Error, Result};
+use crate::frequency::Count;
+
+/// The formats that counts can be exported to.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, ValueEnum)]
+pub enum ExportFormat {
+    /// A table in a SQLite database
+    Sqlite,
+    /// A Parquet file
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Guesses the format from the file name extension, e.g., `.db` or `.sqlite` for SQLite.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "db" | "sqlite" | "sqlite3" => Some(ExportFormat::Sqlite),
+            "parquet" => Some(ExportFormat::Parquet),
+            _ => None,
+        }
+    }
+
+    /// Gives the name of the format, for messages.
+    fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Sqlite => "SQLite",
+            ExportFormat::Parquet => "Parquet",
+        }
+    }
+
+    /// Gives the Cargo feature that the format needs.
+    fn feature(self) -> &'static str {
+        match self {
+            ExportFormat::Sqlite => "sqlite",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Appends the given counts of the given source file, counted at the given time, to the table in the given file,
+/// creating the file if it does not exist.
+///
+/// Gives an [`Error::Io`] if the file cannot be read or written, or is not a database or Parquet file, and an
+/// [`Error::Config`] if a Parquet file has other columns, or if support for the format was left out when building.
+#[cfg_attr(not(all(feature = "sqlite", feature = "parquet")), allow(unused_variables))]
+pub fn export<P: AsRef<Path>>(
+    path: P,
+    format: ExportFormat,
+    source: &str,
+    timestamp: DateTime<Utc>,
+    counts: &[Count<'_>],
+) -> Result<()> {
+    let path = path.as_ref();
+    match format {
+        #[cfg(feature = "sqlite")]
+        ExportFormat::Sqlite => sqlite::append(path, source, timestamp, counts),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => parquet_file::append(path, source, timestamp, counts),
+        #[allow(unreachable_patterns)]
+        _ => Err(Error::Config(format!(
+            "exporting to {} needs the '{}' feature, which was left out when building",
+            format.name(),
+            format.feature()
+        ))),
+    }
+}
+
+#[cfg(any(feature = "sqlite", feature = "parquet"))]
+fn export_error<E: std::error::Error + Send + Sync + 'static>(path: &Path, error: E) -> Error {
+    Error::io(path, std::io::Error::other(error))
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::path::Path;
+
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use rusqlite::{Connection, params};
+
+    use super::export_error;
+    use crate::error::Result;
+    use crate::frequency::Count;
+
+    const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS word_frequencies (
+        word TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        share REAL NOT NULL,
+        relative REAL NOT NULL,
+        source TEXT NOT NULL,
+        run_timestamp TEXT NOT NULL
+    )";
+
+    const INSERT: &str = "INSERT INTO word_frequencies (word, count, share, relative, source, run_timestamp)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+
+    pub(super) fn append(path: &Path, source: &str, timestamp: DateTime<Utc>, counts: &[Count<'_>]) -> Result<()> {
+        insert(path, source, timestamp, counts).map_err(|e| export_error(path, e))
+    }
+
+    fn insert(path: &Path, source: &str, timestamp: DateTime<Utc>, counts: &[Count<'_>]) -> rusqlite::Result<()> {
+        let mut connection = Connection::open(path)?;
+        connection.execute_batch(CREATE_TABLE)?;
+        // Insert all rows in a single transaction, which is much faster, and leaves no partial run on errors
+        let transaction = connection.transaction()?;
+        {
+            let mut statement = transaction.prepare(INSERT)?;
+            let timestamp = timestamp.to_rfc3339_opts(SecondsFormat::Micros, true);
+            for count in counts {
+                let number = count.count as i64;
+                statement.execute(params![count.word, number, count.share, count.relative, source, timestamp])?;
+            }
+        }
+        transaction.commit()
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_file {
+    use std::fs::File;
+    use std::io::ErrorKind;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use chrono::{DateTime, Utc};
+    use parquet::column::reader::get_typed_column_reader;
+    use parquet::data_type::{ByteArray, ByteArrayType, DataType, DoubleType, Int64Type};
+    use parquet::file::reader::{FileReader, RowGroupReader, SerializedFileReader};
+    use parquet::file::writer::{SerializedFileWriter, SerializedRowGroupWriter};
+    use parquet::schema::parser::parse_message_type;
+
+    use super::export_error;
+    use crate::error::{Error, Result};
+    use crate::frequency::Count;
+
+    const SCHEMA: &str = "message word_frequencies {
+        REQUIRED BYTE_ARRAY word (STRING);
+        REQUIRED INT64 count;
+        REQUIRED DOUBLE share;
+        REQUIRED DOUBLE relative;
+        REQUIRED BYTE_ARRAY source (STRING);
+        REQUIRED INT64 run_timestamp (TIMESTAMP(MICROS, true));
+    }";
+
+    /// The values of the columns of a row group.
+    #[derive(Default)]
+    struct Columns {
+        words: Vec<ByteArray>,
+        counts: Vec<i64>,
+        shares: Vec<f64>,
+        relatives: Vec<f64>,
+        sources: Vec<ByteArray>,
+        timestamps: Vec<i64>,
+    }
+
+    impl Columns {
+        fn new(source: &str, timestamp: DateTime<Utc>, counts: &[Count<'_>]) -> Self {
+            Self {
+                words: counts.iter().map(|count| ByteArray::from(count.word)).collect(),
+                counts: counts.iter().map(|count| count.count as i64).collect(),
+                shares: counts.iter().map(|count| count.share).collect(),
+                relatives: counts.iter().map(|count| count.relative).collect(),
+                sources: vec![ByteArray::from(source); counts.len()],
+                timestamps: vec![timestamp.timestamp_micros(); counts.len()],
+            }
+        }
+
+        fn read(row_group: &dyn RowGroupReader) -> parquet::errors::Result<Self> {
+            let rows = row_group.metadata().num_rows() as usize;
+            let mut columns = Self::default();
+            read_column::<ByteArrayType>(row_group, 0, rows, &mut columns.words)?;
+            read_column::<Int64Type>(row_group, 1, rows, &mut columns.counts)?;
+            read_column::<DoubleType>(row_group, 2, rows, &mut columns.shares)?;
+            read_column::<DoubleType>(row_group, 3, rows, &mut columns.relatives)?;
+            read_column::<ByteArrayType>(row_group, 4, rows, &mut columns.sources)?;
+            read_column::<Int64Type>(row_group, 5, rows, &mut columns.timestamps)?;
+            Ok(columns)
+        }
+
+        fn write(&self, writer: &mut SerializedFileWriter<File>) -> parquet::errors::Result<()> {
+            let mut row_group = writer.next_row_group()?;
+            write_column::<ByteArrayType>(&mut row_group, &self.words)?;
+            write_column::<Int64Type>(&mut row_group, &self.counts)?;
+            write_column::<DoubleType>(&mut row_group, &self.shares)?;
+            write_column::<DoubleType>(&mut row_group, &self.relatives)?;
+            write_column::<ByteArrayType>(&mut row_group, &self.sources)?;
+            write_column::<Int64Type>(&mut row_group, &self.timestamps)?;
+            row_group.close()?;
+            Ok(())
+        }
+    }
+
+    fn read_column<T: DataType>(
+        row_group: &dyn RowGroupReader,
+        column: usize,
+        rows: usize,
+        values: &mut Vec<T::T>,
+    ) -> parquet::errors::Result<()> {
+        let mut reader = get_typed_column_reader::<T>(row_group.get_column_reader(column)?);
+        reader.read_records(rows, None, None, values)?;
+        Ok(())
+    }
+
+    fn write_column<T: DataType>(
+        row_group: &mut SerializedRowGroupWriter<'_, File>,
+        values: &[T::T],
+    ) -> parquet::errors::Result<()> {
+        let mut column = row_group.next_column()?.expect("the schema has a column for each vector of values");
+        column.typed::<T>().write_batch(values, None, None)?;
+        column.close()
+    }
+
+    pub(super) fn append(path: &Path, source: &str, timestamp: DateTime<Utc>, counts: &[Count<'_>]) -> Result<()> {
+        let schema = Arc::new(parse_message_type(SCHEMA).expect("the schema is valid"));
+        let mut row_groups = match File::open(path) {
+            Ok(file) => {
+                let reader = SerializedFileReader::new(file).map_err(|e| export_error(path, e))?;
+                if reader.metadata().file_metadata().schema() != schema.as_ref() {
+                    return Err(Error::Config(format!(
+                        "'{}' has other columns than a table of word frequencies",
+                        path.display()
+                    )));
+                }
+                (0..reader.num_row_groups())
+                    .map(|i| Columns::read(reader.get_row_group(i)?.as_ref()))
+                    .collect::<parquet::errors::Result<Vec<Columns>>>()
+                    .map_err(|e| export_error(path, e))?
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::io(path, e)),
+        };
+        row_groups.push(Columns::new(source, timestamp, counts));
+
+        // Write to a new file that replaces the old one when it is complete, so the old rows are never lost
+        let mut temporary_path = path.as_os_str().to_owned();
+        temporary_path.push(".tmp");
+        let file = File::create(&temporary_path).map_err(|e| Error::io(&temporary_path, e))?;
+        let write = || {
+            let mut writer = SerializedFileWriter::new(file, schema, Default::default())?;
+            for columns in &row_groups {
+                columns.write(&mut writer)?;
+            }
+            writer.close()?;
+            Ok(())
+        };
+        write().map_err(|e: parquet::errors::ParquetError| export_error(path, e))?;
+        std::fs::rename(&temporary_path, path).map_err(|e| Error::io(path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(ExportFormat::from_path("counts.db"), Some(ExportFormat::Sqlite));
+        assert_eq!(ExportFormat::from_path("data/counts.SQLite3"), Some(ExportFormat::Sqlite));
+        assert_eq!(ExportFormat::from_path("counts.parquet"), Some(ExportFormat::Parquet));
+        assert_eq!(ExportFormat::from_path("counts.csv"), None);
+        assert_eq!(ExportFormat::from_path("counts"), None);
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "parquet"))]
+    fn counts() -> Vec<Count<'static>> {
+        vec![Count::new("cats", 2).with_shares(3, 2), Count::new("dogs", 1).with_shares(3, 2)]
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "parquet"))]
+    fn timestamp(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    #[cfg(any(feature = "sqlite", feature = "parquet"))]
+    fn temporary_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("word-frequency-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    #[test]
+    fn test_export_without_feature() {
+        let error = export("counts.db", ExportFormat::Sqlite, "a.txt", DateTime::UNIX_EPOCH, &[]).unwrap_err();
+        assert!(matches!(error, Error::Config(message) if message.contains("'sqlite' feature")));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_export_sqlite_appends() {
+        let path = temporary_path("export.db");
+        export(&path, ExportFormat::Sqlite, "a.txt", timestamp(0), &counts()).unwrap();
+        export(&path, ExportFormat::Sqlite, "b.txt", timestamp(60), &counts()[..1]).unwrap();
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let mut statement = connection
+            .prepare("SELECT word, count, share, source, run_timestamp FROM word_frequencies ORDER BY rowid")
+            .unwrap();
+        let rows: Vec<(String, i64, f64, String, String)> = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], ("dogs".into(), 1, 1.0 / 3.0, "a.txt".into(), "1970-01-01T00:00:00.000000Z".into()));
+        assert_eq!(rows[2].3, "b.txt");
+        let total: i64 = connection
+            .query_row("SELECT sum(count) FROM word_frequencies WHERE word = 'cats'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(total, 4);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_export_parquet_appends() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::RowAccessor;
+
+        let path = temporary_path("export.parquet");
+        export(&path, ExportFormat::Parquet, "a.txt", timestamp(0), &counts()).unwrap();
+        export(&path, ExportFormat::Parquet, "b.txt", timestamp(60), &counts()[..1]).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.num_row_groups(), 2);
+        let rows: Vec<_> = reader.get_row_iter(None).unwrap().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1].get_string(0).unwrap(), "dogs");
+        assert_eq!(rows[1].get_long(1).unwrap(), 1);
+        assert_eq!(rows[1].get_double(2).unwrap(), 1.0 / 3.0);
+        assert_eq!(rows[1].get_double(3).unwrap(), 0.5);
+        assert_eq!(rows[2].get_string(4).unwrap(), "b.txt");
+        assert_eq!(rows[2].get_timestamp_micros(5).unwrap(), 60_000_000);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_export_parquet_to_other_file() {
+        let path = temporary_path("other.parquet");
+        std::fs::write(&path, b"not parquet").unwrap();
+        assert!(matches!(
+            export(&path, ExportFormat::Parquet, "a.txt", timestamp(0), &counts()),
+            Err(Error::Io { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
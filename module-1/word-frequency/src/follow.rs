@@ -0,0 +1,173 @@
+//!
+//! Contains code to follow a growing file, like `tail -f`, giving the text appended to it since it was last read.
+//!
+//! The follower handles log rotation, where the file is renamed and a new file is created in its place, and
+//! truncation, where the file is emptied and written from the start again.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::io::Write;
+//! use word_frequency::follow::FileFollower;
+//!
+//! let path = std::env::temp_dir().join("word-frequency-follow-example.log");
+//! std::fs::write(&path, "first line\n").unwrap();
+//! let mut follower = FileFollower::open(&path).unwrap();
+//! assert_eq!(follower.read_appended().unwrap(), "first line\n");
+//!
+//! let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+//! file.write_all(b"second line\nthird ").unwrap();
+//! assert_eq!(follower.read_appended().unwrap(), "second line\n");
+//! file.write_all(b"line\n").unwrap();
+//! assert_eq!(follower.read_appended().unwrap(), "third line\n");
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::fs::{File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Follows a file, giving the complete lines appended to it since the last read.
+#[derive(Debug)]
+pub struct FileFollower {
+    /// The path of the followed file, which may refer to a new file after log rotation
+    path: PathBuf,
+    /// The file we're currently reading
+    file: File,
+    /// The position in the file up to which we have read
+    position: u64,
+    /// The bytes read after the last newline, which are kept until the rest of the line has been written
+    partial_line: Vec<u8>,
+}
+
+impl FileFollower {
+    /// Opens the given file for following, starting at the beginning of the file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path).map_err(|e| Error::io(&path, e))?;
+        Ok(Self {
+            path,
+            file,
+            position: 0,
+            partial_line: Vec::new(),
+        })
+    }
+
+    /// Gives the complete lines written to the file since the last call, or an empty string if nothing new has been
+    /// written. An unterminated last line is kept until its newline has been written, so that a word being written
+    /// is not split in two.
+    ///
+    /// If the file has been truncated, reading restarts from the beginning. If the file has been replaced, e.g., by
+    /// log rotation, the rest of the old file is read before switching to the new one. Invalid UTF-8 is replaced by
+    /// the Unicode replacement character rather than giving an error, so that a single bad line in a log does not
+    /// stop the following.
+    pub fn read_appended(&mut self) -> Result<String> {
+        let mut appended = self.read_to_end()?;
+        if let Ok(metadata) = std::fs::metadata(&self.path) {
+            let current_metadata = self.file.metadata().map_err(|e| Error::io(&self.path, e))?;
+            if !is_same_file(&metadata, &current_metadata) {
+                self.file = File::open(&self.path).map_err(|e| Error::io(&self.path, e))?;
+                self.position = 0;
+                self.flush_partial_line(&mut appended);
+                appended.extend(self.read_to_end()?);
+            } else if metadata.len() < self.position {
+                self.file.seek(SeekFrom::Start(0)).map_err(|e| Error::io(&self.path, e))?;
+                self.position = 0;
+                self.partial_line.clear();
+                appended = self.read_to_end()?;
+            }
+        }
+        self.partial_line.extend(appended);
+        let complete_length = self.partial_line.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        let complete_lines: Vec<u8> = self.partial_line.drain(..complete_length).collect();
+        Ok(String::from_utf8_lossy(&complete_lines).into_owned())
+    }
+
+    fn read_to_end(&mut self) -> Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        let length = self.file.read_to_end(&mut buffer).map_err(|e| Error::io(&self.path, e))?;
+        self.position += length as u64;
+        Ok(buffer)
+    }
+
+    /// Terminates the partial line of a file that will not be written to any more, so that it is not joined with the
+    /// first line of the next file.
+    fn flush_partial_line(&mut self, appended: &mut Vec<u8>) {
+        if !self.partial_line.is_empty() || !appended.is_empty() && !appended.ends_with(b"\n") {
+            appended.push(b'\n');
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn is_same_file(a: &Metadata, b: &Metadata) -> bool {
+    a.created().ok() == b.created().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_truncation() {
+        let path = temp_path("truncation");
+        std::fs::write(&path, "one two\nthree\n").unwrap();
+        let mut follower = FileFollower::open(&path).unwrap();
+        assert_eq!(follower.read_appended().unwrap(), "one two\nthree\n");
+        assert_eq!(follower.read_appended().unwrap(), "");
+        std::fs::write(&path, "four\n").unwrap();
+        assert_eq!(follower.read_appended().unwrap(), "four\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rotation() {
+        let path = temp_path("rotation");
+        let rotated_path = temp_path("rotation.1");
+        std::fs::write(&path, "one\n").unwrap();
+        let mut follower = FileFollower::open(&path).unwrap();
+        assert_eq!(follower.read_appended().unwrap(), "one\n");
+
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(b"two\nthr").unwrap();
+        assert_eq!(follower.read_appended().unwrap(), "two\n");
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(b"ee").unwrap();
+        std::fs::rename(&path, &rotated_path).unwrap();
+        std::fs::write(&path, "four\nfive").unwrap();
+        assert_eq!(follower.read_appended().unwrap(), "three\nfour\n");
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(follower.read_appended().unwrap(), "");
+        std::fs::remove_file(&rotated_path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let path = temp_path("invalid-utf8");
+        std::fs::write(&path, b"caf\xe9\n").unwrap();
+        let mut follower = FileFollower::open(&path).unwrap();
+        assert_eq!(follower.read_appended().unwrap(), "caf\u{fffd}\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_open_missing_file() {
+        let error = FileFollower::open(temp_path("missing")).unwrap_err();
+        assert!(matches!(error, Error::Io { path: Some(_), .. }));
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("word-frequency-follow-test-{}-{}.log", std::process::id(), name))
+    }
+}
@@ -7,60 +7,272 @@
 //!
 //! ```
 //! use std::fs::File;
-//! use word_frequency::frequency::{Count, FrequencyCounter};
+//! use word_frequency::frequency::{Count, FrequencyCounter, Scale};
 //!
 //! let mut frequency_counter = FrequencyCounter::from_file("poem.txt").unwrap();
 //! frequency_counter.read_stop_words("stop-words.txt").unwrap();
 //! let word_frequencies = frequency_counter.count_frequencies();
-//! let sorted_frequencies = FrequencyCounter::sort_frequencies(&word_frequencies, false);
+//! let sorted_frequencies = FrequencyCounter::sort_frequencies(&word_frequencies);
 //! assert_eq!(sorted_frequencies.len(), 15);
-//! assert_eq!(sorted_frequencies[0], Count::new("nobody", 2));
-//! assert_eq!(sorted_frequencies[14], Count::new("somebody", 1));
+//! assert_eq!(sorted_frequencies[0], Count::new("nobody", 2).with_shares(17, 2));
+//! assert_eq!(sorted_frequencies[14], Count::new("somebody", 1).with_shares(17, 2));
+//! assert_eq!(sorted_frequencies[14].format(Scale::Relative), "50.00");
+//! assert_eq!(sorted_frequencies[14].format(Scale::Percent), "5.8824%");
 //! ```
 
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Cursor;
 use std::io::Read;
+use std::sync::Arc;
 
-use crate::tokenizer::{Token, Tokenizer};
+use clap::ValueEnum;
+use rustc_hash::FxHashSet;
+
+use crate::collation::Collation;
+use crate::collocations::Cooccurrences;
+use crate::compression;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::compression::Compression;
+use crate::error::{Error, Result};
+use crate::input_format::InputFormat;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::mapped;
+use crate::mapped::ByteTokenizer;
+use crate::phrases::{PhraseMatcher, Phrases};
+use crate::records::FieldSelector;
+use crate::tokenizer::{Lexeme, Token, Tokenizer};
+use crate::vocabulary::WordCounts;
+
+/// The scales that a [`Count`] can be shown on.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, ValueEnum)]
+pub enum Scale {
+    /// The number of occurrences
+    Absolute,
+    /// Relative to the most common word, between 0 and 100
+    Relative,
+    /// The percentage of all counted words
+    Percent,
+    /// The number of occurrences per million counted words
+    PerMillion,
+}
 
 #[derive(Debug)]
 pub struct FrequencyCounter {
-    tokenizer: Tokenizer,
+    input: Input,
     stop_words: HashSet<String>,
+    /// The stop words with the Fx hash, which is much faster than SipHash for looking up every word, built once and
+    /// shared with the counters
+    stop_word_set: Arc<FxHashSet<String>>,
+    /// The multi-word expressions, shared with the counters
+    phrases: Option<Arc<Phrases>>,
+}
+
+/// The tokens of the input that is left to count, either from a string or from a file mapped into memory.
+#[derive(Debug)]
+enum Input {
+    Text { tokenizer: Tokenizer, len: usize },
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    Mapped(ByteTokenizer),
+}
+
+impl Input {
+    fn text(input: &str) -> Self {
+        Input::Text {
+            tokenizer: Tokenizer::new(input),
+            len: input.len(),
+        }
+    }
+}
+
+impl Input {
+    fn read_into(&mut self, word: &mut String) -> Option<Lexeme> {
+        match self {
+            Input::Text { tokenizer, .. } => tokenizer.read_into(word),
+            Input::Mapped(tokenizer) => tokenizer.read_into(word),
+        }
+    }
+}
+
+impl Iterator for Input {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        match self {
+            Input::Text { tokenizer, .. } => tokenizer.next_token(),
+            Input::Mapped(tokenizer) => tokenizer.next_token(),
+        }
+    }
 }
 
 impl FrequencyCounter {
     /// Creates a new `FrequencyCounter` that works on the given string, with no stop words.
     pub fn new(input: &str) -> Self {
         Self {
-            tokenizer: Tokenizer::new(input),
+            input: Input::text(input),
             stop_words: HashSet::new(),
+            stop_word_set: Arc::default(),
+            phrases: None,
         }
     }
 
     /// Creates a new `FrequencyCounter` by reading from the given `Read` instance and converting the contents into a string.
+    /// Input compressed with gzip, zstd or bzip2 is detected and decompressed while reading, see [`compression`].
     /// The string is converted to lower case before calling the [`Self::new()`] function.
-    pub fn from_reader(mut reader: Box<dyn Read>) -> Result<Self, std::io::Error> {
+    ///
+    /// Gives an [`Error::Decode`] if the input is not valid UTF-8 or the compressed data is corrupt.
+    pub fn from_reader(reader: Box<dyn Read>) -> Result<Self> {
+        Self::from_reader_with_format(reader, InputFormat::Text)
+    }
+
+    /// Works like [`Self::from_reader()`], but first extracts the text from the input in the given format, so that,
+    /// e.g., HTML tags are not counted as words.
+    pub fn from_reader_with_format(reader: Box<dyn Read>, format: InputFormat) -> Result<Self> {
+        Ok(Self::new(&Self::read_text(reader, format)?))
+    }
+
+    /// Reads the text that [`Self::from_reader_with_format()`] counts the words of, i.e., the decompressed input with
+    /// the text extracted from the given format and converted to lower case.
+    pub fn read_text(reader: Box<dyn Read>, format: InputFormat) -> Result<String> {
+        let mut reader = compression::decompress(reader)?;
         let mut input = String::new();
-        reader.read_to_string(&mut input)?;
-        Ok(Self::new(&input.to_lowercase()))
+        reader.read_to_string(&mut input).map_err(Error::reading)?;
+        Ok(format.extract_text(&input).to_lowercase())
+    }
+
+    /// Reads the decompressed input as it is, without decoding it as text, e.g., for counting byte values, see
+    /// [`characters`](crate::characters).
+    pub fn read_bytes(reader: Box<dyn Read>) -> Result<Vec<u8>> {
+        let mut reader = compression::decompress(reader)?;
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input).map_err(Error::reading)?;
+        Ok(input)
+    }
+
+    /// Creates a new `FrequencyCounter` by opening the given file and then calling the
+    /// [`Self::from_reader_with_format()`] function, with the input format guessed from the file name extension, see
+    /// [`InputFormat::from_path()`]. Files with an unknown extension are read as plain text. Errors give the name of
+    /// the file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file(file_name: &str) -> Result<Self> {
+        Self::from_file_with_format(file_name, InputFormat::from_path(file_name).unwrap_or(InputFormat::Text))
+    }
+
+    /// Creates a new `FrequencyCounter` by opening the given file and then calling the
+    /// [`Self::from_reader_with_format()`] function with the given input format.
+    ///
+    /// Uncompressed plain text files are instead mapped into memory and tokenized directly from there, see
+    /// [`mapped`], which counts the same words without keeping a copy of the whole text.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file_with_format(file_name: &str, format: InputFormat) -> Result<Self> {
+        let file = File::open(file_name).map_err(|e| Error::io(file_name, e))?;
+        let frequency_counter = match mapped::map_file(&file) {
+            Some(map) if format == InputFormat::Text && Compression::detect(&map) == Compression::None => {
+                ByteTokenizer::new(map).map(|tokenizer| Self {
+                    input: Input::Mapped(tokenizer),
+                    stop_words: HashSet::new(),
+                    stop_word_set: Arc::default(),
+                    phrases: None,
+                })
+            }
+            Some(map) => Self::from_reader_with_format(Box::new(Cursor::new(map)), format),
+            None => Self::from_reader_with_format(Box::new(file), format),
+        };
+        frequency_counter.map_err(|e| e.with_path(file_name))
     }
 
-    /// Creates a new `FrequencyCounter` by opening the given file and then calling the [`Self::from_reader()`] function.
-    pub fn from_file(file_name: &str) -> Result<Self, std::io::Error> {
-        let reader = File::open(file_name)?;
-        Self::from_reader(Box::new(reader))
+    /// Creates one `FrequencyCounter` for each group of records read from the given `Read` instance, counting only the
+    /// text of the field chosen by the [`FieldSelector`]. Compressed input is decompressed like in
+    /// [`Self::from_reader()`], and the text is converted to lower case.
+    pub fn from_records(reader: Box<dyn Read>, selector: &FieldSelector) -> Result<BTreeMap<Option<String>, Self>> {
+        let groups = selector.read_groups(compression::decompress(reader)?)?;
+        Ok(groups.into_iter().map(|(group, text)| (group, Self::new(&text.to_lowercase()))).collect())
     }
 
     /// Reads a comma-separated file with "stop words", i.e., small words that you want to ignore when counting.
-    pub fn read_stop_words(&mut self, file_name: &str) -> Result<(), std::io::Error> {
-        self.stop_words = std::fs::read_to_string(file_name)?
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .collect();
-        return Ok(());
+    /// The words may be spread over several lines, and are converted to lower case to match the input.
+    ///
+    /// Gives an [`Error::StopWords`] if an entry can never match a word, e.g., because it contains spaces or digits.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_stop_words(&mut self, file_name: &str) -> Result<()> {
+        let contents = std::fs::read_to_string(file_name).map_err(|e| Error::reading(e).with_path(file_name))?;
+        let mut stop_words = HashSet::new();
+        for (line_number, line) in contents.lines().enumerate() {
+            for entry in line.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+                if !entry.chars().all(|c| c.is_alphabetic() || c == '\'') {
+                    return Err(Error::StopWords {
+                        path: file_name.into(),
+                        line: line_number + 1,
+                        message: format!("'{}' is not a single word", entry),
+                    });
+                }
+                stop_words.insert(entry.to_lowercase());
+            }
+        }
+        self.set_stop_words(stop_words);
+        Ok(())
+    }
+
+    /// Gives the length in bytes of the input given to this `FrequencyCounter`, after decompressing it and extracting
+    /// the text.
+    pub fn input_len(&self) -> usize {
+        match &self.input {
+            Input::Text { len, .. } => *len,
+            Input::Mapped(tokenizer) => tokenizer.len(),
+        }
+    }
+
+    /// Gives the tokens of the input that is left to count, e.g., to time tokenizing and counting separately with
+    /// [`Self::count_tokens()`].
+    pub fn tokens(&mut self) -> impl Iterator<Item = Token> + '_ {
+        &mut self.input
+    }
+
+    /// Gives the stop words, i.e., the words that are ignored when counting.
+    pub fn stop_words(&self) -> &HashSet<String> {
+        &self.stop_words
+    }
+
+    /// Gives the stop words with the Fx hash, to share them with a counter.
+    #[cfg(feature = "async")]
+    pub(crate) fn stop_word_set(&self) -> &Arc<FxHashSet<String>> {
+        &self.stop_word_set
+    }
+
+    /// Replaces the stop words, e.g., with the ones read by another `FrequencyCounter`. The stop words should be in
+    /// lower case to match the input.
+    pub fn set_stop_words(&mut self, stop_words: HashSet<String>) {
+        self.stop_word_set = Arc::new(stop_words.iter().cloned().collect());
+        self.stop_words = stop_words;
+    }
+
+    /// Reads a file with multi-word expressions, one per line, that are counted as single terms, see [`Phrases`].
+    ///
+    /// Gives an [`Error::Config`] if a line is not a sequence of at least two words.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_phrases(&mut self, file_name: &str) -> Result<()> {
+        self.phrases = Some(Arc::new(Phrases::read(file_name)?));
+        Ok(())
+    }
+
+    /// Gives the multi-word expressions that are counted as single terms, if any.
+    pub fn phrases(&self) -> Option<&Phrases> {
+        self.phrases.as_deref()
+    }
+
+    /// Gives the multi-word expressions, to share them with a counter.
+    #[cfg(feature = "async")]
+    pub(crate) fn shared_phrases(&self) -> Option<&Arc<Phrases>> {
+        self.phrases.as_ref()
+    }
+
+    /// Replaces the multi-word expressions that are counted as single terms, e.g., with the ones read by another
+    /// `FrequencyCounter`.
+    pub fn set_phrases(&mut self, phrases: Phrases) {
+        self.phrases = Some(Arc::new(phrases));
     }
 
     /// Counts the number of offurrences of each word in the input string, ignoring the stop words.
@@ -68,56 +280,323 @@ impl FrequencyCounter {
     /// Returns a mappring from words to the number of occurrences of that word.
     pub fn count_frequencies(&mut self) -> HashMap<String, usize> {
         let mut word_frequencies: HashMap<String, usize> = HashMap::<String, usize>::new();
-        while let Some(token) = self.tokenizer.next_token() {
+        self.add_frequencies(&mut word_frequencies);
+        word_frequencies
+    }
+
+    /// Works like [`Self::count_frequencies()`], but adds the number of occurrences of each word to the counts already
+    /// in the given mapping, so that counts can be accumulated over several inputs given with [`Self::feed()`].
+    pub fn add_frequencies(&mut self, word_frequencies: &mut HashMap<String, usize>) {
+        self.count_word_counts(1).add_to(word_frequencies);
+    }
+
+    /// Counts the number of occurrences of each n-gram, i.e., each sequence of `n` consecutive words, in the input
+    /// string. The words of an n-gram are joined with single spaces. N-grams do not span punctuation or numbers, and
+    /// n-grams containing a stop word are ignored, so for `n == 1` this gives the same result as
+    /// [`Self::count_frequencies()`]. A multi-word expression given with [`Self::set_phrases()`] counts as one word.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use word_frequency::frequency::FrequencyCounter;
+    ///
+    /// let text = "lady catherine and lady catherine, the daughter. lady lucas";
+    /// let mut frequency_counter = FrequencyCounter::new(text);
+    /// frequency_counter.read_stop_words("stop-words.txt").unwrap();
+    /// let bigram_frequencies = frequency_counter.count_ngrams(2);
+    /// assert_eq!(bigram_frequencies["lady catherine"], 2);
+    /// assert_eq!(bigram_frequencies["lady lucas"], 1);
+    /// assert!(!bigram_frequencies.contains_key("daughter lady"));
+    /// assert!(!bigram_frequencies.contains_key("catherine and"));
+    /// ```
+    pub fn count_ngrams(&mut self, n: usize) -> HashMap<String, usize> {
+        let mut ngram_frequencies = HashMap::new();
+        self.add_ngram_frequencies(n, &mut ngram_frequencies);
+        ngram_frequencies
+    }
+
+    /// Works like [`Self::count_ngrams()`], but adds the number of occurrences of each n-gram to the counts already
+    /// in the given mapping.
+    pub fn add_ngram_frequencies(&mut self, n: usize, ngram_frequencies: &mut HashMap<String, usize>) {
+        self.count_word_counts(n).add_to(ngram_frequencies);
+    }
+
+    /// Works like [`Self::count_ngrams()`], but gives the counts as [`WordCounts`], where each distinct word or n-gram
+    /// is stored once and counted by ID. This is what the other counting functions are built on, and it avoids
+    /// creating a `String` for every distinct word when the counts are only sorted, see [`WordCounts::sorted()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use word_frequency::frequency::FrequencyCounter;
+    ///
+    /// let mut frequency_counter = FrequencyCounter::new("to be or not to be");
+    /// let word_counts = frequency_counter.count_word_counts(1);
+    /// assert_eq!(word_counts.count("be"), 2);
+    /// assert_eq!(word_counts.sorted()[0].word, "be");
+    /// ```
+    pub fn count_word_counts(&mut self, n: usize) -> WordCounts {
+        let mut word_counts = WordCounts::new();
+        let input = &mut self.input;
+        let mut counter = NgramCounter::new(Arc::clone(&self.stop_word_set), n).with_phrases(self.phrases.clone());
+        counter.add_all(|word| input.read_into(word), &mut word_counts);
+        counter.finish(&mut word_counts);
+        word_counts
+    }
+
+    /// Works like [`Self::count_ngrams()`], but counts the given tokens instead of the input string, e.g., to time
+    /// tokenizing and counting separately. The stop words of this `FrequencyCounter` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use word_frequency::frequency::FrequencyCounter;
+    /// use word_frequency::tokenizer::{Token, Tokenizer};
+    ///
+    /// let tokens: Vec<Token> = Tokenizer::new("to be or not to be").collect();
+    /// let word_frequencies = FrequencyCounter::new("").count_tokens(tokens, 1);
+    /// assert_eq!(word_frequencies["be"], 2);
+    /// ```
+    pub fn count_tokens<I: IntoIterator<Item = Token>>(&self, tokens: I, n: usize) -> HashMap<String, usize> {
+        let mut word_counts = WordCounts::new();
+        let mut tokens = tokens.into_iter();
+        let next_lexeme = |word: &mut String| {
+            Some(match tokens.next()? {
+                Token::Word(text) => {
+                    *word = text;
+                    Lexeme::Word
+                }
+                Token::Whitespace => Lexeme::Whitespace,
+                Token::Number(_) | Token::Numeral(_) | Token::Punctuation(_) => Lexeme::Other,
+            })
+        };
+        let mut counter = NgramCounter::new(Arc::clone(&self.stop_word_set), n).with_phrases(self.phrases.clone());
+        counter.add_all(next_lexeme, &mut word_counts);
+        counter.finish(&mut word_counts);
+        let mut ngram_frequencies = HashMap::new();
+        word_counts.add_to(&mut ngram_frequencies);
+        ngram_frequencies
+    }
+
+    /// Counts the pairs of words that occur at most `window` words apart, for finding collocations. Stop words are
+    /// skipped, and numbers and punctuation are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use word_frequency::frequency::FrequencyCounter;
+    ///
+    /// let mut frequency_counter = FrequencyCounter::new("strong tea, strong coffee and powerful tea");
+    /// let cooccurrences = frequency_counter.count_cooccurrences(2);
+    /// assert_eq!(cooccurrences.count("strong", "tea"), 1);
+    /// assert_eq!(cooccurrences.count("strong", "coffee"), 1);
+    /// assert_eq!(cooccurrences.count("powerful", "tea"), 1);
+    /// assert_eq!(cooccurrences.count("coffee", "tea"), 0);
+    /// ```
+    pub fn count_cooccurrences(&mut self, window: usize) -> Cooccurrences {
+        let mut cooccurrences = Cooccurrences::new(window);
+        for token in &mut self.input {
             if let Token::Word(word) = token
-                && !self.stop_words.contains(&word)
+                && !self.stop_word_set.contains(&word)
             {
-                word_frequencies.entry(word).and_modify(|count| *count += 1).or_insert(1);
+                cooccurrences.add_word(&word);
             }
         }
-        word_frequencies
+        cooccurrences
+    }
+
+    /// Replaces the input that is left to count with the given string, keeping the stop words. This lets the same
+    /// `FrequencyCounter` count text that arrives in pieces, e.g., lines appended to a log file. Unlike
+    /// [`Self::from_reader()`], the string is not converted to lower case.
+    pub fn feed(&mut self, input: &str) {
+        self.input = Input::text(input);
     }
 
     /// Given a mapping from words to frequencies as produced by [`FrequencyCounter::count_frequencies()`],
-    /// returns a vector of [`Count`] objects sorted in descending order by count. If `relative` is true,
-    /// gives the count as relative between 0 and 100, if false gives the count as the actual number of
-    /// occurrences.
-    pub fn sort_frequencies<'a>(word_frequencies: &'a HashMap<String, usize>, relative: bool) -> Vec<Count<'a>> {
-        let mut v: Vec<Count> = word_frequencies.iter().map(|wc| Count::new(wc.0, *wc.1)).collect();
+    /// returns a vector of [`Count`] objects sorted in descending order by count, and alphabetically for words with
+    /// the same count. Each count also gives the share of all counted words, and the count relative to the most
+    /// common word.
+    pub fn sort_frequencies<'a>(word_frequencies: &'a HashMap<String, usize>) -> Vec<Count<'a>> {
+        let total = word_frequencies.values().sum();
+        let max = word_frequencies.values().copied().max().unwrap_or(0);
+        let mut v: Vec<Count> =
+            word_frequencies.iter().map(|wc| Count::new(wc.0, *wc.1).with_shares(total, max)).collect();
         v.sort();
-        if relative { FrequencyCounter::relativize_counts(v) } else { v }
+        v
+    }
+
+    /// Works like [`Self::sort_frequencies()`], but sorts words with the same count with the given collation, e.g.,
+    /// by the rules of a locale, instead of by their bytes.
+    pub fn sort_frequencies_with<'a>(
+        word_frequencies: &'a HashMap<String, usize>,
+        collation: &Collation,
+    ) -> Vec<Count<'a>> {
+        let mut v = Self::sort_frequencies(word_frequencies);
+        v.sort_by(|a, b| collation.compare_counts(a, b));
+        v
     }
+}
 
-    fn relativize_counts(sorted_frequencies: Vec<Count>) -> Vec<Count> {
-        let mut relative = Vec::new();
-        if sorted_frequencies.is_empty() {
-            return relative;
+/// Counts the words or n-grams of a sequence of tokens, which may be given in several pieces, e.g., as they arrive
+/// from a stream, since the words at the end of one piece start the n-grams of the next.
+#[derive(Debug)]
+pub(crate) struct NgramCounter {
+    stop_words: Arc<FxHashSet<String>>,
+    n: usize,
+    /// The last words read, which start the next n-gram
+    words: VecDeque<String>,
+    ngram: String,
+    /// Replaces multi-word expressions with single terms before they are counted
+    phrases: Option<PhraseMatcher>,
+}
+
+impl NgramCounter {
+    pub(crate) fn new(stop_words: Arc<FxHashSet<String>>, n: usize) -> Self {
+        Self {
+            stop_words,
+            n: n.max(1),
+            words: VecDeque::with_capacity(n),
+            ngram: String::new(),
+            phrases: None,
         }
-        if !sorted_frequencies.is_sorted() {
-            panic!("sorted_frequences is not sorted");
+    }
+
+    /// Counts the given multi-word expressions as single words. The words are then only counted when the expressions
+    /// among them are known, i.e., at punctuation or numbers, or when [`Self::finish()`] is called.
+    pub(crate) fn with_phrases(mut self, phrases: Option<Arc<Phrases>>) -> Self {
+        self.phrases = phrases.map(PhraseMatcher::new);
+        self
+    }
+
+    /// Counts the tokens given by `next_lexeme`, which reads the next token into a buffer like
+    /// [`Tokenizer::read_into()`], until it gives `None`.
+    pub(crate) fn add_all(
+        &mut self,
+        mut next_lexeme: impl FnMut(&mut String) -> Option<Lexeme>,
+        word_counts: &mut WordCounts,
+    ) {
+        let mut word = String::new();
+        while let Some(lexeme) = next_lexeme(&mut word) {
+            self.add(lexeme, &word, word_counts);
         }
-        let max = sorted_frequencies[0].count as f32;
-        for f in sorted_frequencies {
-            let relative_frequency = (f.count as f32 / max) * 100.0;
-            relative.push(Count::new(f.word, relative_frequency.round() as usize));
+    }
+
+    /// Counts a single token, where `word` is the text if it is a word.
+    pub(crate) fn add(&mut self, lexeme: Lexeme, word: &str, word_counts: &mut WordCounts) {
+        match lexeme {
+            Lexeme::Word => match &mut self.phrases {
+                Some(phrases) => phrases.push(word),
+                None => self.add_word(word, word_counts),
+            },
+            Lexeme::Whitespace => {}
+            Lexeme::Other => {
+                self.finish(word_counts);
+                self.words.clear();
+            }
+        }
+    }
+
+    /// Counts the words that are held back while looking for multi-word expressions, at the end of the input.
+    pub(crate) fn finish(&mut self, word_counts: &mut WordCounts) {
+        if let Some(mut phrases) = self.phrases.take() {
+            phrases.drain(|term| self.add_word(term, word_counts));
+            self.phrases = Some(phrases);
+        }
+    }
+
+    fn add_word(&mut self, word: &str, word_counts: &mut WordCounts) {
+        if self.stop_words.contains(word) {
+            self.words.clear();
+        } else if self.n == 1 {
+            word_counts.add(word);
+        } else {
+            self.words.push_back(word.to_string());
+            if self.words.len() == self.n {
+                self.ngram.clear();
+                for (i, word) in self.words.iter().enumerate() {
+                    if i > 0 {
+                        self.ngram.push(' ');
+                    }
+                    self.ngram.push_str(word);
+                }
+                word_counts.add(&self.ngram);
+                self.words.pop_front();
+            }
         }
-        relative
     }
 }
 
-/// Contains a word and a count of the number of occurrences of that word.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+/// Contains a word and a count of the number of occurrences of that word, together with the count as shares of all
+/// counted words and of the most common word. Counts are compared by their words and numbers of occurrences only, since
+/// the shares follow from those.
+#[derive(Debug, Copy, Clone)]
 pub struct Count<'a> {
     pub word: &'a str,
     pub count: usize,
+    /// The count as a fraction of the total number of counted words, between 0 and 1
+    pub share: f64,
+    /// The count as a fraction of the count of the most common word, between 0 and 1
+    pub relative: f64,
 }
 
 impl<'a> Count<'a> {
+    /// Creates a new `Count` with both shares 0, see [`Self::with_shares()`].
     pub fn new(word: &'a str, count: usize) -> Self {
-        Self { word, count }
+        Self {
+            word,
+            count,
+            share: 0.0,
+            relative: 0.0,
+        }
+    }
+
+    /// Sets the shares of the count, given the total number of counted words and the count of the most common word.
+    pub fn with_shares(mut self, total: usize, max: usize) -> Self {
+        self.share = if total == 0 { 0.0 } else { self.count as f64 / total as f64 };
+        self.relative = if max == 0 { 0.0 } else { self.count as f64 / max as f64 };
+        self
+    }
+
+    /// Gives the count on the given scale, e.g., the number of occurrences per million words.
+    pub fn value(&self, scale: Scale) -> f64 {
+        match scale {
+            Scale::Absolute => self.count as f64,
+            Scale::Relative => self.relative * 100.0,
+            Scale::Percent => self.share * 100.0,
+            Scale::PerMillion => self.share * 1_000_000.0,
+        }
+    }
+
+    /// Formats the count on the given scale, as an integer for absolute counts and with decimals otherwise.
+    pub fn format(&self, scale: Scale) -> String {
+        match scale {
+            Scale::Absolute => self.count.to_string(),
+            Scale::Relative | Scale::PerMillion => format!("{:.2}", self.value(scale)),
+            Scale::Percent => format!("{:.4}%", self.value(scale)),
+        }
+    }
+
+    /// Gives the count as a JSON object like `{"word": "cats", "count": 2, "percent": 50.0, "per_million": 500000.0,
+    /// "relative": 100.0}`, as given by the HTTP server and the WebAssembly module.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "word": self.word,
+            "count": self.count,
+            "percent": self.value(Scale::Percent),
+            "per_million": self.value(Scale::PerMillion),
+            "relative": self.value(Scale::Relative),
+        })
     }
 }
 
+impl PartialEq for Count<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.word == other.word && self.count == other.count
+    }
+}
+
+impl Eq for Count<'_> {}
+
 impl<'a> PartialOrd for Count<'a> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -133,3 +612,18 @@ impl<'a> Ord for Count<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_eq_agrees_with_ord() {
+        let count = Count::new("a", 1);
+        let with_shares = Count::new("a", 1).with_shares(2, 1);
+        assert_eq!(count.cmp(&with_shares), Ordering::Equal);
+        assert_eq!(count, with_shares);
+        assert_ne!(count, Count::new("a", 2));
+        assert_ne!(count, Count::new("b", 1));
+    }
+}
@@ -0,0 +1,556 @@
+//!
+//! Contains code to extract the plain text from structured input formats before it is tokenized.
+//!
+//! Without this, counting the words of an HTML page would also count tag names and attribute values, and counting
+//! a subtitle file would count the timestamps of the subtitles.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::input_format::InputFormat;
+//!
+//! let format = InputFormat::from_path("index.html").unwrap();
+//! assert_eq!(format, InputFormat::Html);
+//! assert_eq!(format.extract_text("<p class=\"intro\">Fish &amp; chips</p>").trim(), "Fish & chips");
+//! ```
+
+use std::path::Path;
+
+use clap::ValueEnum;
+
+/// The input formats that we know how to extract text from.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, ValueEnum)]
+pub enum InputFormat {
+    /// Plain text, used as is
+    Text,
+    /// HTML, where tags, comments, scripts and style sheets are removed and entities are decoded
+    Html,
+    /// XML, where tags and comments are removed and entities are decoded
+    Xml,
+    /// Markdown, where code blocks, link targets, inline HTML and formatting characters are removed
+    Markdown,
+    /// SRT or WebVTT subtitles, where cue numbers, timestamps and cue settings are removed
+    Subtitles,
+}
+
+/// Elements of an HTML document whose contents are not text meant to be read.
+const HTML_RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// File name extensions of compressed files, that are ignored when detecting the format from a file name.
+const COMPRESSION_EXTENSIONS: [&str; 5] = ["gz", "gzip", "zst", "zstd", "bz2"];
+
+impl InputFormat {
+    /// Guesses the input format from the extension of the given file name, ignoring any extension that marks the
+    /// file as compressed, so that both `page.html` and `page.html.gz` are detected as HTML. Returns `None` if the
+    /// extension is missing or unknown.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        let mut extension = path.extension()?.to_str()?.to_lowercase();
+        if COMPRESSION_EXTENSIONS.contains(&extension.as_str()) {
+            extension = Path::new(path.file_stem()?).extension()?.to_str()?.to_lowercase();
+        }
+        Self::from_extension(&extension)
+    }
+
+    /// Gives the input format for the given file name extension, without the leading dot, or `None` if unknown.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "txt" | "text" => Some(InputFormat::Text),
+            "html" | "htm" | "xhtml" => Some(InputFormat::Html),
+            "xml" => Some(InputFormat::Xml),
+            "md" | "markdown" => Some(InputFormat::Markdown),
+            "srt" | "vtt" => Some(InputFormat::Subtitles),
+            _ => None,
+        }
+    }
+
+    /// Extracts the text from the given input in this format.
+    pub fn extract_text(&self, input: &str) -> String {
+        match self {
+            InputFormat::Text => input.to_string(),
+            InputFormat::Html => strip_markup(input, &HTML_RAW_TEXT_ELEMENTS),
+            InputFormat::Xml => strip_markup(input, &[]),
+            InputFormat::Markdown => strip_markdown(input),
+            InputFormat::Subtitles => strip_subtitles(input),
+        }
+    }
+}
+
+/// Removes tags, comments and processing instructions from HTML or XML, keeping the contents of CDATA sections and
+/// decoding entities. The contents of the `raw_text_elements` are removed together with their tags.
+///
+/// Every removed tag is replaced by a space, so that the text of adjacent block elements is not joined into one word.
+fn strip_markup(input: &str, raw_text_elements: &[&str]) -> String {
+    let mut text = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            text.push_str(&cdata[..end]);
+            rest = cdata.get(end + 3..).unwrap_or("");
+        } else if let Some(tag_name) = start_tag_name(rest) {
+            let tag_end = find_tag_end(rest);
+            let self_closing = rest[..tag_end].ends_with("/>");
+            rest = &rest[tag_end..];
+            if !self_closing
+                && raw_text_elements
+                    .iter()
+                    .any(|element| element.eq_ignore_ascii_case(tag_name))
+            {
+                rest = skip_raw_text(rest, tag_name);
+            }
+        } else if rest[1..].starts_with(['/', '!', '?']) {
+            rest = &rest[find_tag_end(rest)..];
+        } else {
+            // Not a tag, e.g., "a < b", so keep the character as text
+            text.push('<');
+            rest = &rest[1..];
+            continue;
+        }
+        text.push(' ');
+    }
+    text.push_str(&decode_entities(rest));
+    text
+}
+
+/// Gives the name of the start tag at the beginning of `input`, which must start with '<', if there is one.
+fn start_tag_name(input: &str) -> Option<&str> {
+    let name_length = input[1..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == ':'))
+        .unwrap_or(input.len() - 1);
+    let name = &input[1..1 + name_length];
+    if name.starts_with(|c: char| c.is_alphabetic()) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// Gives the position just after the '>' that ends the tag at the beginning of `input`, skipping any '>' inside
+/// quoted attribute values. Gives the length of the input if the tag is not terminated.
+fn find_tag_end(input: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    input.len()
+}
+
+/// Skips the raw text of an element such as `<script>`, up to and including the end tag.
+fn skip_raw_text<'a>(input: &'a str, tag_name: &str) -> &'a str {
+    let end_tag = format!("</{}", tag_name.to_ascii_lowercase());
+    match input.to_ascii_lowercase().find(&end_tag) {
+        Some(start) => &input[start + find_tag_end(&input[start..])..],
+        None => "",
+    }
+}
+
+/// Decodes HTML and XML character references, both named ones like `&amp;` and numeric ones like `&#233;` and
+/// `&#xE9;`. Unknown or malformed references are kept as they are.
+pub fn decode_entities(input: &str) -> String {
+    let mut text = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('&') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        // Only look a little past the '&' for the ';', since searching the rest of the input for every '&' is slow
+        let end = rest.as_bytes()[1..].iter().take(33).position(|&b| b == b';');
+        let decoded = end.and_then(|end| {
+            let name = &rest[1..end + 1];
+            decode_entity(name).map(|c| (c, end + 2))
+        });
+        match decoded {
+            Some((c, length)) => {
+                text.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                text.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Decodes a single character reference, given without the leading '&' and trailing ';'.
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse::<u32>().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "shy" => '\u{ad}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "deg" => '°',
+        "middot" => '·',
+        "bull" => '•',
+        "euro" => '€',
+        "pound" => '£',
+        "szlig" => 'ß',
+        _ => return decode_accented_letter(name),
+    };
+    Some(c)
+}
+
+/// Decodes the named references for accented Latin letters, like `&eacute;` and `&Ouml;`.
+fn decode_accented_letter(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    let letter = chars.next()?;
+    let accent = chars.as_str();
+    let (lower, upper) = match (letter.to_ascii_lowercase(), accent) {
+        ('a', "grave") => ('à', 'À'),
+        ('a', "acute") => ('á', 'Á'),
+        ('a', "circ") => ('â', 'Â'),
+        ('a', "tilde") => ('ã', 'Ã'),
+        ('a', "uml") => ('ä', 'Ä'),
+        ('a', "ring") => ('å', 'Å'),
+        ('a', "elig") => ('æ', 'Æ'),
+        ('c', "cedil") => ('ç', 'Ç'),
+        ('e', "grave") => ('è', 'È'),
+        ('e', "acute") => ('é', 'É'),
+        ('e', "circ") => ('ê', 'Ê'),
+        ('e', "uml") => ('ë', 'Ë'),
+        ('i', "grave") => ('ì', 'Ì'),
+        ('i', "acute") => ('í', 'Í'),
+        ('i', "circ") => ('î', 'Î'),
+        ('i', "uml") => ('ï', 'Ï'),
+        ('n', "tilde") => ('ñ', 'Ñ'),
+        ('o', "grave") => ('ò', 'Ò'),
+        ('o', "acute") => ('ó', 'Ó'),
+        ('o', "circ") => ('ô', 'Ô'),
+        ('o', "tilde") => ('õ', 'Õ'),
+        ('o', "uml") => ('ö', 'Ö'),
+        ('o', "slash") => ('ø', 'Ø'),
+        ('u', "grave") => ('ù', 'Ù'),
+        ('u', "acute") => ('ú', 'Ú'),
+        ('u', "circ") => ('û', 'Û'),
+        ('u', "uml") => ('ü', 'Ü'),
+        ('y', "acute") => ('ý', 'Ý'),
+        ('y', "uml") => ('ÿ', 'Ÿ'),
+        _ => return None,
+    };
+    Some(if letter.is_ascii_uppercase() { upper } else { lower })
+}
+
+/// Removes Markdown syntax, keeping the text that would be shown when the document is rendered. Code blocks,
+/// link targets, link reference definitions and inline HTML are removed entirely.
+fn strip_markdown(input: &str) -> String {
+    let mut text = String::with_capacity(input.len());
+    let mut code_fence: Option<&str> = None;
+    for line in input.lines() {
+        let trimmed = line.trim_start();
+        if let Some(fence) = code_fence {
+            if trimmed.starts_with(fence) {
+                code_fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            code_fence = Some(&trimmed[..3]);
+            continue;
+        }
+        if line.starts_with("    ")
+            || line.starts_with('\t')
+            || is_markdown_rule(trimmed)
+            || is_link_definition(trimmed)
+        {
+            text.push('\n');
+            continue;
+        }
+        let content = strip_block_markers(trimmed);
+        text.push_str(&strip_markup(&strip_inline_markdown(content), &HTML_RAW_TEXT_ELEMENTS));
+        text.push('\n');
+    }
+    text
+}
+
+/// Tells if the line is a thematic break like `---` or `***`, or the underline of a setext heading.
+fn is_markdown_rule(line: &str) -> bool {
+    let line = line.trim_end();
+    let Some(first) = line.chars().next() else {
+        return false;
+    };
+    matches!(first, '-' | '*' | '_' | '=') && line.chars().all(|c| c == first || c == ' ') && line.len() >= 3
+}
+
+/// Tells if the line is a link reference definition like `[id]: https://example.com "Title"`.
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[') && line.find("]:").is_some_and(|end| !line[1..end].contains(']'))
+}
+
+/// Removes the markers of headings, block quotes and list items at the start of a line.
+fn strip_block_markers(mut line: &str) -> &str {
+    loop {
+        let stripped = if let Some(rest) = line.strip_prefix('>') {
+            rest
+        } else if line.starts_with('#') {
+            let rest = line.trim_start_matches('#');
+            if rest.is_empty() || rest.starts_with(' ') {
+                rest
+            } else {
+                line
+            }
+        } else if let Some(rest) = line.strip_prefix(['-', '*', '+']).filter(|rest| rest.starts_with(' ')) {
+            rest
+        } else {
+            let digits = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+            match line[digits..].strip_prefix(['.', ')']) {
+                Some(rest) if digits > 0 && rest.starts_with(' ') => rest,
+                _ => line,
+            }
+        };
+        if stripped.len() == line.len() {
+            return line;
+        }
+        line = stripped.trim_start();
+    }
+}
+
+/// Removes inline Markdown: link and image targets, autolinks, code spans markers and emphasis markers.
+fn strip_inline_markdown(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '!' if rest.starts_with("![") => rest = &rest[1..],
+            ']' => {
+                rest = &rest[1..];
+                if let Some(target) = rest.strip_prefix(['(', '[']) {
+                    let close = if rest.starts_with('(') { ')' } else { ']' };
+                    rest = target.find(close).map_or("", |end| &target[end + 1..]);
+                }
+            }
+            '<' if is_autolink(rest) => rest = &rest[rest.find('>').map_or(rest.len(), |end| end + 1)..],
+            '_' if is_intraword(&text, &rest[1..]) => {
+                text.push(c);
+                rest = &rest[1..];
+            }
+            '[' | '*' | '_' | '`' | '~' => rest = &rest[1..],
+            '\\' if rest.len() > 1 => {
+                let escaped = rest[1..].chars().next().unwrap_or('\\');
+                text.push(escaped);
+                rest = &rest[1 + escaped.len_utf8()..];
+            }
+            _ => {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    text
+}
+
+/// Tells if a character is surrounded by alphanumeric characters, given the text before and after it, in which
+/// case an underscore is part of a word like `snake_case` rather than emphasis.
+fn is_intraword(before: &str, after: &str) -> bool {
+    before.ends_with(char::is_alphanumeric) && after.starts_with(char::is_alphanumeric)
+}
+
+/// Tells if the input starts with an autolink like `<https://example.com>` or `<someone@example.com>`.
+fn is_autolink(input: &str) -> bool {
+    match input.find('>') {
+        Some(end) => {
+            let target = &input[1..end];
+            !target.contains(char::is_whitespace) && (target.contains("://") || target.contains('@'))
+        }
+        None => false,
+    }
+}
+
+/// Removes everything but the text of the cues from SRT or WebVTT subtitles.
+///
+/// Both formats consist of blocks separated by blank lines, where each cue block has a line with timestamps like
+/// `00:01:02,500 --> 00:01:04,000` followed by the text. Lines before the timestamps are cue numbers or
+/// identifiers, and blocks without timestamps are headers, comments or style definitions, so all of those are
+/// removed. Tags like `<i>`, `<v Speaker>` and `<00:01:03.000>`, and SRT style overrides like `{\an8}`, are also
+/// removed.
+fn strip_subtitles(input: &str) -> String {
+    let mut text = String::with_capacity(input.len());
+    let mut in_cue_text = false;
+    for line in input.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            in_cue_text = false;
+        } else if line.contains("-->") {
+            in_cue_text = true;
+        } else if in_cue_text {
+            let line = remove_delimited(&remove_delimited(line, "{\\", "}"), "<", ">");
+            text.push_str(&decode_entities(&line));
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Removes everything from each `open` delimiter up to and including the following `close` delimiter. An `open`
+/// delimiter without a matching `close` delimiter is kept as text.
+fn remove_delimited(line: &str, open: &str, close: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(open) {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match rest.find(close) {
+            Some(end) => rest = &rest[end + close.len()..],
+            None => break,
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(InputFormat::from_path("poem.txt"), Some(InputFormat::Text));
+        assert_eq!(InputFormat::from_path("dir/index.HTML"), Some(InputFormat::Html));
+        assert_eq!(InputFormat::from_path("feed.xml.gz"), Some(InputFormat::Xml));
+        assert_eq!(InputFormat::from_path("README.md"), Some(InputFormat::Markdown));
+        assert_eq!(InputFormat::from_path("movie.srt"), Some(InputFormat::Subtitles));
+        assert_eq!(InputFormat::from_path("movie.vtt.zst"), Some(InputFormat::Subtitles));
+        assert_eq!(InputFormat::from_path("archive.gz"), None);
+        assert_eq!(InputFormat::from_path("Makefile"), None);
+        assert_eq!(InputFormat::from_path("-"), None);
+    }
+
+    #[test]
+    fn test_extract_text_from_html() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>Cats</title>
+<style>body { color: red; }</style>
+<script type="text/javascript">if (a < b) { document.write("<p>no</p>"); }</script>
+</head>
+<body class="main"><!-- a comment --><p title="a > b">Caf&eacute; &amp; <b>cr&#232;me</b>&#x21;</p>
+<img src="cat.png" alt="cat"/>a &lt; b &unknown; & c</body></html>"#;
+        assert_eq!(
+            words(&InputFormat::Html.extract_text(html)),
+            ["Cats", "Café", "&", "crème", "!", "a", "<", "b", "&unknown;", "&", "c"]
+        );
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("&lt;&#65;&#x42;&unknown;&;&amp"), "<AB&unknown;&;&amp");
+        // References of up to 32 characters are decoded
+        assert_eq!(decode_entities(&format!("&#{}65;", "0".repeat(29))), "A");
+        let too_long = format!("&#{}65;", "0".repeat(30));
+        assert_eq!(decode_entities(&too_long), too_long);
+        // Many '&' without a ';' take linear time, since only the characters right after each '&' are searched
+        let query = "a=1&b=2".repeat(200_000);
+        assert_eq!(decode_entities(&query), query);
+    }
+
+    #[test]
+    fn test_extract_text_from_xml() {
+        let xml =
+            r#"<?xml version="1.0"?><poem author="Emily"><line>I'm nobody!</line><![CDATA[Who <are> you?]]></poem>"#;
+        assert_eq!(
+            words(&InputFormat::Xml.extract_text(xml)),
+            ["I'm", "nobody!", "Who", "<are>", "you?"]
+        );
+    }
+
+    #[test]
+    fn test_extract_text_from_markdown() {
+        let markdown = "# The *Title*\n\
+            Title\n\
+            =====\n\
+            > Some **bold** and _emphasized_ `code` text.\n\
+            \n\
+            - A [link](https://example.com/path) and ![an image](cat.png \"Cat\")\n\
+            1. See [the docs][docs] or <https://example.com>\n\
+            \n\
+            ```rust\n\
+            fn main() {}\n\
+            ```\n\
+            ---\n\
+            [docs]: https://example.com/docs\n\
+            An <span class=\"x\">inline</span> tag, an escaped \\*star\\* and snake_case";
+        assert_eq!(
+            words(&InputFormat::Markdown.extract_text(markdown)),
+            [
+                "The",
+                "Title",
+                "Title",
+                "Some",
+                "bold",
+                "and",
+                "emphasized",
+                "code",
+                "text.",
+                "A",
+                "link",
+                "and",
+                "an",
+                "image",
+                "See",
+                "the",
+                "docs",
+                "or",
+                "An",
+                "inline",
+                "tag,",
+                "an",
+                "escaped",
+                "*star*",
+                "and",
+                "snake_case"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_text_from_srt() {
+        let srt = "1\r\n00:00:01,000 --> 00:00:04,000\r\n<i>Hello</i> there,\r\n{\\an8}General Kenobi!\r\n\r\n\
+            2\r\n00:00:05,000 --> 00:00:06,500\r\n42 is the answer\r\n";
+        assert_eq!(
+            words(&InputFormat::Subtitles.extract_text(srt)),
+            ["Hello", "there,", "General", "Kenobi!", "42", "is", "the", "answer"]
+        );
+    }
+
+    #[test]
+    fn test_extract_text_from_vtt() {
+        let vtt = "WEBVTT - Some title\n\nNOTE This is a comment\n\nSTYLE\n::cue { color: yellow }\n\n\
+            intro\n00:01.000 --> 00:04.000 align:start position:10%\n<v Roger>Hi <c.loud>all</c><00:02.000> you\n";
+        assert_eq!(words(&InputFormat::Subtitles.extract_text(vtt)), ["Hi", "all", "you"]);
+    }
+
+    fn words(text: &str) -> Vec<&str> {
+        text.split_whitespace().collect()
+    }
+}
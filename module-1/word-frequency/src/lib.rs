@@ -1,2 +1,31 @@
 pub mod tokenizer;
 pub mod frequency;
+pub mod compression;
+pub mod input_format;
+pub mod records;
+pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod follow;
+pub mod trends;
+pub mod svg;
+pub mod collocations;
+pub mod variants;
+pub mod characters;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod config;
+pub mod mapped;
+pub mod vocabulary;
+pub mod export;
+pub mod query;
+pub mod collation;
+pub mod phrases;
+#[cfg(feature = "async")]
+pub mod async_reader;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "wasm")]
+pub mod wasm;
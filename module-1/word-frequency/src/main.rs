@@ -1,47 +1,734 @@
-use std::{fs::File, io::Read};
+//!
+//! Counts the number of occurrences of words in a file and prints the most common.
+//!
+//! # Exit codes
+//!
+//! - 0: success
+//! - 2: invalid command line arguments
+//! - 3: the stop words file contains an entry that is not a word
+//! - 4: a file could not be opened or read
+//! - 5: the input is not valid UTF-8, the compressed data is corrupt, or a CSV or JSON record is malformed
+//! - 6: the options do not match the input, e.g., a CSV column that does not exist, or the config file is invalid
+//!
+//! # Configuration
+//!
+//! The options can also be set in a `word-frequency.toml` file in the current directory or in
+//! `word-frequency/config.toml` in the XDG config directory, see [`word_frequency::config`]. Options given on the
+//! command line override the settings in the file, and `--print-config` shows the effective settings.
 
-use clap::Parser;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read};
+use std::time::{Instant, SystemTime};
+use std::{fs::File, thread, time::Duration};
 
-use word_frequency::frequency::{Count, FrequencyCounter};
+use chrono::TimeDelta;
+use clap::{ArgGroup, CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use word_frequency::characters::{self, Unit};
+use word_frequency::collation::{Collation, CollationKind};
+use word_frequency::collocations::Measure;
+use word_frequency::config::{self, Config};
+use word_frequency::error::{Error, Result};
+use word_frequency::export::{self, ExportFormat};
+use word_frequency::follow::FileFollower;
+use word_frequency::frequency::{Count, FrequencyCounter, Scale};
+use word_frequency::input_format::InputFormat;
+use word_frequency::query::{Query, SortKey};
+use word_frequency::records::{FieldSelector, RecordFormat};
+#[cfg(feature = "server")]
+use word_frequency::server::Server;
+use word_frequency::svg::{self, ChartKind};
+use word_frequency::tokenizer::Token;
+use word_frequency::trends::{self, WindowedCounter};
+#[cfg(feature = "tui")]
+use word_frequency::tui::App;
+use word_frequency::variants::{self, Cluster, VariantClusterer};
+
+/// The file with stop words, i.e., words that are not counted.
+const STOP_WORDS_FILE: &str = "stop-words.txt";
+
+/// Exit code when the stop words file contains an entry that is not a word.
+const EXIT_STOP_WORDS: i32 = 3;
+/// Exit code when a file could not be opened or read.
+const EXIT_IO: i32 = 4;
+/// Exit code when the input could not be decoded.
+const EXIT_DECODE: i32 = 5;
+/// Exit code when the options do not match the input.
+const EXIT_CONFIG: i32 = 6;
+
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  Success
+  2  Invalid command line arguments
+  3  The stop words file contains an entry that is not a word
+  4  A file could not be opened or read
+  5  The input is not valid UTF-8, the compressed data is corrupt, or a CSV or JSON record is malformed
+  6  The options do not match the input, e.g., a CSV column that does not exist, or the config file is invalid
+
+Options can also be set in word-frequency.toml in the current directory, or in word-frequency/config.toml in the
+XDG config directory, with the long option names as keys, e.g., 'num-words = 10'.";
 
 #[derive(Parser)]
-#[command(author, version, about)]
+#[command(author, version, about, after_help = EXIT_CODES_HELP)]
+#[command(group(ArgGroup::new("field").args(["column", "json_field"])))]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     #[clap(short, long, default_value = "25", help = "The number of words to print")]
     num_words: usize,
-    #[clap(short, long, default_value = "false", help = "Print counts as relative between 0 and 100")]
+    #[clap(long, default_value = "0", help = "Skip this many words before printing, e.g., to see the next page")]
+    offset: usize,
+    #[clap(long, help = "Print only the words occurring at least this many times")]
+    min_count: Option<usize>,
+    #[clap(long, help = "Print only the words occurring at most this many times")]
+    max_count: Option<usize>,
+    #[clap(long, help = "Print only the words with at least this many characters")]
+    min_length: Option<usize>,
+    #[clap(long, help = "Print only the words matching this regular expression, e.g., '^un' or 'ing$'")]
+    regex: Option<String>,
+    #[clap(long, value_enum, default_value = "count", help = "The order to print the words in")]
+    sort: SortKey,
+    #[clap(long, help = "Print the least common or the shortest words first, when sorting by count or length")]
+    ascending: bool,
+    #[clap(
+        long,
+        value_enum,
+        default_value = "bytes",
+        help = "How to compare words when sorting alphabetically and for words with the same count"
+    )]
+    collation: CollationKind,
+    #[clap(
+        long,
+        conflicts_with = "collation",
+        help = "Compare words by the rules of this locale, e.g., 'de' or 'sv-SE', like '--collation unicode'"
+    )]
+    locale: Option<String>,
+    #[clap(long, help = "Ignore case when comparing words, so 'Apple' sorts next to 'apple'")]
+    ignore_case: bool,
+    #[clap(
+        short,
+        long,
+        default_value = "false",
+        conflicts_with = "scale",
+        help = "Print counts as relative between 0 and 100, like '--scale relative'"
+    )]
     relative: bool,
-    #[clap(help = "The name of the file to read, use '-' for stdin")]
-    file_name: String,
+    #[clap(long, value_enum, default_value = "absolute", help = "The scale to print the counts on")]
+    scale: Scale,
+    #[clap(long, default_value = STOP_WORDS_FILE, help = "The comma-separated file with stop words, to ignore")]
+    stop_words: String,
+    #[clap(long, conflicts_with = "stop_words", help = "Count all words, including the stop words")]
+    no_stop_words: bool,
+    #[clap(
+        long,
+        value_name = "FILE",
+        conflicts_with = "unit",
+        help = "A file with multi-word expressions, one per line, like 'new york', to count as single words"
+    )]
+    phrases: Option<String>,
+    #[clap(
+        long,
+        default_value = "1",
+        value_parser = clap::value_parser!(u16).range(1..),
+        conflicts_with_all = ["follow", "timestamp_format"],
+        help = "Count sequences of this many consecutive words instead of single words"
+    )]
+    ngrams: u16,
+    #[clap(
+        long,
+        value_name = "DISTANCE",
+        conflicts_with_all = ["field", "follow", "timestamp_format"],
+        help = "Merge the counts of spelling variants within this edit distance of a more common word"
+    )]
+    variants: Option<usize>,
+    #[clap(
+        long,
+        value_enum,
+        conflicts_with_all = ["ngrams", "variants", "field", "follow", "timestamp_format"],
+        help = "Count characters, classes of characters or byte values instead of words"
+    )]
+    unit: Option<Unit>,
+    #[clap(long, value_enum, help = "The format of the input, guessed from the file name extension if not given")]
+    input_format: Option<InputFormat>,
+    #[clap(long, conflicts_with = "input_format", help = "Read the input as CSV and count the words in this column")]
+    column: Option<String>,
+    #[clap(
+        long,
+        conflicts_with = "input_format",
+        help = "Read the input as JSON Lines and count the words in this field, e.g., 'message.body'"
+    )]
+    json_field: Option<String>,
+    #[clap(long, requires = "field", help = "Count the words separately for each value of this column or field")]
+    group_by: Option<String>,
+    #[clap(
+        short,
+        long,
+        conflicts_with_all = ["input_format", "field"],
+        help = "Keep reading lines appended to the file, like 'tail -f', and redraw the counts periodically"
+    )]
+    follow: bool,
+    #[clap(long, default_value = "2", requires = "follow", help = "The seconds between redraws when following")]
+    refresh_interval: f64,
+    #[clap(
+        long,
+        conflicts_with_all = ["input_format", "field", "follow"],
+        help = "Count words per time window, using this format for the timestamp at the start of each line, \
+            e.g., '%Y-%m-%d %H:%M:%S'"
+    )]
+    timestamp_format: Option<String>,
+    #[clap(
+        long,
+        default_value = "1h",
+        value_parser = trends::parse_duration,
+        requires = "timestamp_format",
+        help = "The length of the time windows, e.g., '30s', '15m', '1h' or '1d'"
+    )]
+    window: TimeDelta,
+    #[clap(
+        long,
+        value_parser = trends::parse_duration,
+        requires = "timestamp_format",
+        help = "The time between the starts of overlapping, sliding windows, by default the window length"
+    )]
+    window_step: Option<TimeDelta>,
+    #[clap(
+        long,
+        default_value = "5",
+        requires = "timestamp_format",
+        help = "The number of trending words to print for each time window"
+    )]
+    trending: usize,
+    #[clap(
+        long,
+        conflicts_with_all = ["field", "follow", "timestamp_format"],
+        help = "Also write a chart of the most common words to this SVG file"
+    )]
+    svg: Option<String>,
+    #[clap(long, value_enum, default_value = "cloud", requires = "svg", help = "The kind of chart to write")]
+    chart: ChartKind,
+    #[clap(
+        long,
+        conflicts_with_all = ["field", "follow", "timestamp_format"],
+        help = "Also append all counts, with the file name and the time, to this SQLite database or Parquet file"
+    )]
+    export: Option<String>,
+    #[clap(
+        long,
+        value_enum,
+        requires = "export",
+        help = "The format to export to, guessed from the file name extension if not given"
+    )]
+    export_format: Option<ExportFormat>,
+    #[clap(
+        long,
+        conflicts_with_all = ["unit", "field", "follow", "timestamp_format"],
+        help = "Print the time taken to read, tokenize, count and sort to stderr"
+    )]
+    profile: bool,
+    #[cfg(feature = "tui")]
+    #[clap(
+        long,
+        conflicts_with_all = ["ngrams", "variants", "unit", "field", "follow", "timestamp_format", "svg", "profile"],
+        help = "Explore the words interactively in a terminal user interface"
+    )]
+    tui: bool,
+    #[clap(long, help = "Print the effective settings from the config file and the command line as TOML, and exit")]
+    print_config: bool,
+    #[clap(
+        required_unless_present = "print_config",
+        help = "The name of the file to read, use '-' for stdin, may be compressed with gzip, zstd or bzip2"
+    )]
+    file_name: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Runs an HTTP server where you POST text to /frequencies and get the most common words back as JSON
+    #[cfg(feature = "server")]
+    Serve {
+        #[clap(long, default_value = "127.0.0.1:8080", help = "The address to listen on")]
+        address: String,
+        #[clap(long, default_value = STOP_WORDS_FILE, help = "The comma-separated file with stop words")]
+        stop_words: String,
+    },
+    /// Lists the pairs of words that occur near each other more often than by chance
+    Collocations {
+        #[clap(short, long, help = "List only the pairs containing this word")]
+        word: Option<String>,
+        #[clap(
+            long,
+            default_value = "2",
+            value_parser = clap::value_parser!(u16).range(1..),
+            help = "Count pairs of words at most this many words apart"
+        )]
+        window: u16,
+        #[clap(long, value_enum, default_value = "log-likelihood", help = "The measure to rank the pairs by")]
+        measure: Measure,
+        #[clap(long, default_value = "3", help = "Ignore pairs occurring fewer times than this")]
+        min_count: usize,
+        #[clap(short, long, default_value = "25", help = "The number of pairs to print")]
+        num_words: usize,
+        #[clap(long, default_value = STOP_WORDS_FILE, help = "The comma-separated file with stop words, to ignore")]
+        stop_words: String,
+        #[clap(long, conflicts_with = "stop_words", help = "Count all words, including the stop words")]
+        no_stop_words: bool,
+        #[clap(long, value_enum, help = "The format of the input, guessed from the file name extension if not given")]
+        input_format: Option<InputFormat>,
+        #[clap(help = "The name of the file to read, use '-' for stdin, may be compressed with gzip, zstd or bzip2")]
+        file_name: String,
+    },
+}
+
+impl Args {
+    /// Gives the name of the file to read, which clap requires when there is no subcommand.
+    fn file_name(&self) -> &str {
+        self.file_name.as_deref().expect("the file name is required without a subcommand")
+    }
+
+    fn scale(&self) -> Scale {
+        if self.relative { Scale::Relative } else { self.scale }
+    }
+
+    fn stop_words_file(&self) -> Option<&str> {
+        if self.no_stop_words { None } else { Some(&self.stop_words) }
+    }
+
+    /// Gives the query that selects the words to print from the sorted counts.
+    fn query(&self) -> Result<Query> {
+        let mut query = Query::new().sort(self.sort).offset(self.offset).limit(self.num_words);
+        if let Some(min_count) = self.min_count {
+            query = query.min_count(min_count);
+        }
+        if let Some(max_count) = self.max_count {
+            query = query.max_count(max_count);
+        }
+        if let Some(min_length) = self.min_length {
+            query = query.min_length(min_length);
+        }
+        if let Some(regex) = &self.regex {
+            query = query.regex(regex)?;
+        }
+        if self.ascending {
+            query = query.ascending();
+        }
+        Ok(query.collation(self.collation()?))
+    }
+
+    /// Gives the collation to compare words with, which is Unicode collation if a locale is given, since `--locale`
+    /// cannot be combined with `--collation`.
+    fn collation(&self) -> Result<Collation> {
+        let kind = if self.locale.is_some() { CollationKind::Unicode } else { self.collation };
+        Collation::new(kind, self.locale.as_deref(), self.ignore_case)
+    }
+
+    fn field_selector(&self) -> Option<FieldSelector> {
+        let selector = match (&self.column, &self.json_field) {
+            (Some(column), _) => FieldSelector::new(RecordFormat::Csv, column),
+            (None, Some(json_field)) => FieldSelector::new(RecordFormat::JsonLines, json_field),
+            (None, None) => return None,
+        };
+        Some(match &self.group_by {
+            Some(group_by) => selector.group_by(group_by),
+            None => selector,
+        })
+    }
 }
 
 fn main() {
-    handle_file(Args::parse().file_name.as_str(), Args::parse().num_words, Args::parse().relative);
+    if let Err(error) = parse_args().and_then(|args| run(&args)) {
+        print_error_and_exit(error);
+    }
+}
+
+/// Parses the command line, with the defaults of the options taken from the config file if there is one, except for
+/// the options that conflict with the command line. Exits after printing the settings if `--print-config` is given,
+/// like clap does for `--help`.
+fn parse_args() -> Result<Args> {
+    let config = config::find().map(|path| Config::read(&path)).transpose()?;
+    let mut command = Args::command();
+    if let Some(config) = &config {
+        command = config.apply(command)?;
+    }
+    let mut matches = command.clone().get_matches();
+    let config = match config {
+        Some(config) => {
+            let remaining = config.without_conflicts(&command, &matches);
+            if remaining != config {
+                command = remaining.apply(Args::command())?;
+                matches = command.clone().get_matches();
+            }
+            Some(remaining)
+        }
+        None => None,
+    };
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if args.print_config {
+        if let Some(config) = &config {
+            println!("# Settings from {} and the command line", config.path().display());
+        }
+        print!("{}", config::effective_settings(&command, &matches, config.as_ref()));
+        std::process::exit(0);
+    }
+    Ok(args)
+}
+
+fn run(args: &Args) -> Result<()> {
+    if let Some(command) = &args.command {
+        return run_command(command);
+    }
+    if args.follow {
+        return follow_file(args);
+    }
+    #[cfg(feature = "tui")]
+    if args.tui {
+        return explore_file(args);
+    }
+    if let Some(timestamp_format) = &args.timestamp_format {
+        return handle_windows(args, timestamp_format);
+    }
+    if let Some(unit) = args.unit {
+        return handle_characters(args, unit);
+    }
+    match args.field_selector() {
+        Some(selector) => handle_records(args, &selector),
+        None => handle_file(args),
+    }
+}
+
+fn run_command(command: &Command) -> Result<()> {
+    match command {
+        #[cfg(feature = "server")]
+        Command::Serve { address, stop_words } => {
+            let server = Server::bind(address, Some(stop_words))?;
+            eprintln!("Listening on http://{}/frequencies", server.local_addr());
+            server.run()
+        }
+        Command::Collocations {
+            word,
+            window,
+            measure,
+            min_count,
+            num_words,
+            stop_words,
+            no_stop_words,
+            input_format,
+            file_name,
+        } => {
+            let input_format = input_format.or_else(|| InputFormat::from_path(file_name)).unwrap_or(InputFormat::Text);
+            let mut frequency_counter = FrequencyCounter::from_reader_with_format(open_input(file_name)?, input_format)
+                .map_err(|e| with_input_path(e, file_name))?;
+            if !no_stop_words {
+                frequency_counter.read_stop_words(stop_words)?;
+            }
+            let cooccurrences = frequency_counter.count_cooccurrences((*window).into());
+            // The input is converted to lower case, so the target word must be too
+            let word = word.as_deref().map(str::to_lowercase);
+            let collocations = cooccurrences.collocations(*measure, word.as_deref(), *min_count);
+            for collocation in collocations.iter().take(*num_words) {
+                println!(
+                    "{} {} - {} - {:.2}",
+                    collocation.first, collocation.second, collocation.count, collocation.score
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+fn handle_file(args: &Args) -> Result<()> {
+    let query = args.query()?;
+    let file_name = args.file_name();
+    let input_format = args.input_format.or_else(|| InputFormat::from_path(file_name)).unwrap_or(InputFormat::Text);
+    let mut profile = Profile::default();
+    let start = Instant::now();
+    let mut frequency_counter = if file_name == "-" {
+        FrequencyCounter::from_reader_with_format(open_input(file_name)?, input_format)?
+    } else {
+        FrequencyCounter::from_file_with_format(file_name, input_format)?
+    };
+    profile.add("read", start, frequency_counter.input_len(), ItemUnit::Bytes);
+    if let Some(stop_words_file) = args.stop_words_file() {
+        frequency_counter.read_stop_words(stop_words_file)?;
+    }
+    if let Some(phrases_file) = &args.phrases {
+        frequency_counter.read_phrases(phrases_file)?;
+    }
+    let mut word_frequencies = if args.profile {
+        // Tokenize before counting, rather than while counting, so that the phases can be timed separately
+        let start = Instant::now();
+        let tokens: Vec<Token> = frequency_counter.tokens().collect();
+        profile.add("tokenize", start, tokens.len(), ItemUnit::Tokens);
+        let start = Instant::now();
+        let word_frequencies = frequency_counter.count_tokens(tokens, args.ngrams.into());
+        profile.add("count", start, word_frequencies.len(), ItemUnit::DistinctWords);
+        word_frequencies
+    } else {
+        frequency_counter.count_ngrams(args.ngrams.into())
+    };
+    let clusters = args.variants.map(|max_distance| VariantClusterer::new(max_distance).cluster(&word_frequencies));
+    if let Some(clusters) = &clusters {
+        word_frequencies = variants::merged_frequencies(clusters);
+    }
+    let start = Instant::now();
+    let sorted_frequencies = FrequencyCounter::sort_frequencies(&word_frequencies);
+    profile.add("sort", start, sorted_frequencies.len(), ItemUnit::Words);
+    write_export(args, &sorted_frequencies)?;
+    let results = query.apply(sorted_frequencies);
+    write_svg(args, &results)?;
+    match &clusters {
+        Some(clusters) => print_results_with_variants(args.scale(), &results, clusters),
+        None => print_results(args.scale(), &results),
+    }
+    if args.profile {
+        profile.print();
+    }
+    Ok(())
+}
+
+fn handle_characters(args: &Args, unit: Unit) -> Result<()> {
+    let query = args.query()?;
+    let file_name = args.file_name();
+    let input_format = args.input_format.or_else(|| InputFormat::from_path(file_name)).unwrap_or(InputFormat::Text);
+    let input = FrequencyCounter::read_bytes(open_input(file_name)?).map_err(|e| with_input_path(e, file_name))?;
+    let frequencies = characters::count(unit, &input, input_format).map_err(|e| with_input_path(e, file_name))?;
+    let sorted_frequencies = FrequencyCounter::sort_frequencies(&frequencies);
+    write_export(args, &sorted_frequencies)?;
+    let results = query.apply(sorted_frequencies);
+    write_svg(args, &results)?;
+    print_results(args.scale(), &results);
+    Ok(())
 }
 
-fn handle_file(file_name: &str, num_words: usize, relative: bool) {
-    let reader: Box<dyn Read>;
+/// Writes a chart of the given counts, which are the ones printed, if `--svg` is given.
+fn write_svg(args: &Args, results: &[Count<'_>]) -> Result<()> {
+    if let Some(svg_file) = &args.svg {
+        std::fs::write(svg_file, svg::render(args.chart, results)).map_err(|e| Error::io(svg_file, e))?;
+    }
+    Ok(())
+}
+
+fn write_export(args: &Args, sorted_frequencies: &[Count<'_>]) -> Result<()> {
+    if let Some(export_file) = &args.export {
+        let format = args.export_format.or_else(|| ExportFormat::from_path(export_file)).ok_or_else(|| {
+            Error::Config(format!("cannot tell the format of '{}', use --export-format", export_file))
+        })?;
+        let source = if args.file_name() == "-" { "stdin" } else { args.file_name() };
+        export::export(export_file, format, source, SystemTime::now().into(), sorted_frequencies)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tui")]
+fn explore_file(args: &Args) -> Result<()> {
+    let file_name = args.file_name();
+    let input_format = args.input_format.or_else(|| InputFormat::from_path(file_name)).unwrap_or(InputFormat::Text);
+    let text =
+        FrequencyCounter::read_text(open_input(file_name)?, input_format).map_err(|e| with_input_path(e, file_name))?;
+    let mut frequency_counter = FrequencyCounter::new("");
+    if let Some(stop_words_file) = args.stop_words_file() {
+        frequency_counter.read_stop_words(stop_words_file)?;
+    }
+    let title = if file_name == "-" { "stdin" } else { file_name };
+    App::new(title, text, frequency_counter.stop_words().clone(), args.scale()).run()
+}
+
+fn handle_records(args: &Args, selector: &FieldSelector) -> Result<()> {
+    let query = args.query()?;
+    let reader = open_input(args.file_name())?;
+    let frequency_counters =
+        FrequencyCounter::from_records(reader, selector).map_err(|e| with_input_path(e, args.file_name()))?;
+    for (i, (group, mut frequency_counter)) in frequency_counters.into_iter().enumerate() {
+        if let Some(stop_words_file) = args.stop_words_file() {
+            frequency_counter.read_stop_words(stop_words_file)?;
+        }
+        if let Some(phrases_file) = &args.phrases {
+            frequency_counter.read_phrases(phrases_file)?;
+        }
+        let word_frequencies = frequency_counter.count_ngrams(args.ngrams.into());
+        let sorted_frequencies = FrequencyCounter::sort_frequencies(&word_frequencies);
+        if args.group_by.is_some() {
+            if i > 0 {
+                println!();
+            }
+            println!("{}:", group.as_deref().unwrap_or("(none)"));
+        }
+        print_results(args.scale(), &query.apply(sorted_frequencies));
+    }
+    Ok(())
+}
+
+fn handle_windows(args: &Args, timestamp_format: &str) -> Result<()> {
+    let query = args.query()?;
+    let mut counter = WindowedCounter::new(timestamp_format, args.window, args.window_step.unwrap_or(args.window))?;
+    if let Some(stop_words_file) = args.stop_words_file() {
+        counter.read_stop_words(stop_words_file)?;
+    }
+    if let Some(phrases_file) = &args.phrases {
+        counter.read_phrases(phrases_file)?;
+    }
+    counter
+        .read_lines(open_input(args.file_name())?)
+        .map_err(|e| with_input_path(e, args.file_name()))?;
+    for (i, window) in counter.windows().iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let total: usize = window.word_frequencies.values().sum();
+        println!("{} - {}: {} words", window.start, window.end, total);
+        print_results(args.scale(), &query.apply(FrequencyCounter::sort_frequencies(&window.word_frequencies)));
+        let previous = counter.previous_window(window);
+        let trending = trends::trending(&previous.word_frequencies, &window.word_frequencies, args.trending);
+        if !trending.is_empty() {
+            let trending: Vec<String> = trending.iter().map(|t| format!("{} (+{})", t.word, t.increase())).collect();
+            println!("Trending: {}", trending.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn follow_file(args: &Args) -> Result<()> {
+    if args.file_name() == "-" {
+        return Err(Error::Config("--follow needs the name of a file, not stdin".to_string()));
+    }
+    let refresh_interval = Duration::try_from_secs_f64(args.refresh_interval)
+        .map_err(|e| Error::Config(format!("invalid refresh interval {}: {}", args.refresh_interval, e)))?;
+    let query = args.query()?;
+    let mut follower = FileFollower::open(args.file_name())?;
+    let mut frequency_counter = FrequencyCounter::new("");
+    if let Some(stop_words_file) = args.stop_words_file() {
+        frequency_counter.read_stop_words(stop_words_file)?;
+    }
+    if let Some(phrases_file) = &args.phrases {
+        frequency_counter.read_phrases(phrases_file)?;
+    }
+    let mut word_frequencies = HashMap::new();
+    let mut previous_frequencies = HashMap::new();
+    loop {
+        frequency_counter.feed(&follower.read_appended()?.to_lowercase());
+        frequency_counter.add_frequencies(&mut word_frequencies);
+        print_follow_results(args, &query, &word_frequencies, &previous_frequencies);
+        previous_frequencies.clone_from(&word_frequencies);
+        thread::sleep(refresh_interval);
+    }
+}
+
+/// Prints the most common words, with the number of new occurrences since the last refresh, clearing the terminal
+/// first so that the table is redrawn in place.
+fn print_follow_results(
+    args: &Args,
+    query: &Query,
+    word_frequencies: &HashMap<String, usize>,
+    previous: &HashMap<String, usize>,
+) {
+    if std::io::stdout().is_terminal() {
+        print!("\x1b[2J\x1b[H");
+    }
+    let total: usize = word_frequencies.values().sum();
+    let new: usize = total - previous.values().sum::<usize>();
+    println!("{} - {} words, {} new", args.file_name(), total, new);
+    for count in query.apply(FrequencyCounter::sort_frequencies(word_frequencies)) {
+        let delta = word_frequencies[count.word] - previous.get(count.word).copied().unwrap_or(0);
+        if delta > 0 {
+            println!("{} - {} (+{})", count.word, count.format(args.scale()), delta);
+        } else {
+            println!("{} - {}", count.word, count.format(args.scale()));
+        }
+    }
+    println!();
+}
+
+fn open_input(file_name: &str) -> Result<Box<dyn Read>> {
     if file_name == "-" {
-        reader = Box::new(std::io::stdin());
+        Ok(Box::new(std::io::stdin()))
     } else {
-        reader = Box::new(File::open(file_name).unwrap_or_else(|e| print_error_and_exit(e, 2)));
+        Ok(Box::new(File::open(file_name).map_err(|e| Error::io(file_name, e))?))
+    }
+}
+
+fn with_input_path(error: Error, file_name: &str) -> Error {
+    if file_name == "-" { error } else { error.with_path(file_name) }
+}
+
+fn print_results(scale: Scale, results: &[Count<'_>]) {
+    for count in results {
+        println!("{} - {}", count.word, count.format(scale));
+    }
+}
+
+fn print_results_with_variants(scale: Scale, results: &[Count<'_>], clusters: &[Cluster]) {
+    let variants: HashMap<&str, &[(String, usize)]> =
+        clusters.iter().map(|cluster| (cluster.canonical.as_str(), cluster.variants.as_slice())).collect();
+    for count in results {
+        let variants: Vec<String> = variants[count.word].iter().map(|(word, n)| format!("{} {}", word, n)).collect();
+        if variants.is_empty() {
+            println!("{} - {}", count.word, count.format(scale));
+        } else {
+            println!("{} - {} ({})", count.word, count.format(scale), variants.join(", "));
+        }
     }
-    let mut frequency_counter = FrequencyCounter::from_reader(reader).unwrap_or_else(|e| print_error_and_exit(e, 2));
-    frequency_counter
-        .read_stop_words("stop-words.txt")
-        .unwrap_or_else(|e| print_error_and_exit(e, 3));
-    let word_frequencies = frequency_counter.count_frequencies();
-    let sorted_frequencies = FrequencyCounter::sort_frequencies(&word_frequencies, relative);
-    print_results(num_words, sorted_frequencies);
 }
 
-fn print_results(num_words: usize, sorted_frequencies: Vec<Count<'_>>) {
-    for i in 0..std::cmp::min(num_words, sorted_frequencies.len()) {
-        println!("{} - {}", sorted_frequencies[i].word, sorted_frequencies[i].count);
+/// The time taken by each phase of counting, printed with `--profile`.
+#[derive(Default)]
+struct Profile {
+    /// The name of each phase, its duration, and the number and unit of the items it produced
+    phases: Vec<(&'static str, Duration, usize, ItemUnit)>,
+}
+
+/// The units of the items produced by a phase of counting.
+#[derive(Clone, Copy)]
+enum ItemUnit {
+    Bytes,
+    Tokens,
+    DistinctWords,
+    Words,
+}
+
+impl ItemUnit {
+    fn name(self) -> &'static str {
+        match self {
+            ItemUnit::Bytes => "bytes",
+            ItemUnit::Tokens => "tokens",
+            ItemUnit::DistinctWords => "distinct words",
+            ItemUnit::Words => "words",
+        }
+    }
+
+    /// Formats the number of items per second as a throughput, for the units where that is meaningful.
+    fn rate(self, items_per_second: f64) -> String {
+        match self {
+            ItemUnit::Bytes => format!("{:.1} MB/s", items_per_second / 1_000_000.0),
+            ItemUnit::Tokens => format!("{:.1} M tokens/s", items_per_second / 1_000_000.0),
+            ItemUnit::DistinctWords | ItemUnit::Words => String::new(),
+        }
+    }
+}
+
+impl Profile {
+    fn add(&mut self, phase: &'static str, start: Instant, items: usize, unit: ItemUnit) {
+        self.phases.push((phase, start.elapsed(), items, unit));
+    }
+
+    /// Prints the phases to stderr, so that the results can still be piped.
+    fn print(&self) {
+        eprintln!();
+        for (phase, duration, items, unit) in &self.phases {
+            let seconds = duration.as_secs_f64();
+            let rate = unit.rate(if seconds > 0.0 { *items as f64 / seconds } else { f64::INFINITY });
+            eprintln!("{:<9} {:>10.3} ms  {:>10} {:<15} {}", phase, seconds * 1000.0, items, unit.name(), rate);
+        }
+        let total: Duration = self.phases.iter().map(|(_, duration, _, _)| *duration).sum();
+        eprintln!("{:<9} {:>10.3} ms", "total", total.as_secs_f64() * 1000.0);
     }
 }
 
-fn print_error_and_exit(error: std::io::Error, exit_code: i32) -> ! {
-    eprintln!("Error opening file: {}", error);
+fn print_error_and_exit(error: Error) -> ! {
+    eprintln!("{}", error);
+    let exit_code = match error {
+        Error::StopWords { .. } => EXIT_STOP_WORDS,
+        Error::Io { .. } => EXIT_IO,
+        Error::Decode { .. } => EXIT_DECODE,
+        Error::Config(_) => EXIT_CONFIG,
+    };
     std::process::exit(exit_code);
 }
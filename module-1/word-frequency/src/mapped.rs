@@ -0,0 +1,541 @@
+//!
+//! Contains code to tokenize a file mapped into memory, without first copying it into a string and then into the
+//! vector of characters that [`Tokenizer`] works on.
+//!
+//! The input is split into segments at ASCII characters that cannot be part of a word or a number, like spaces and
+//! punctuation. The segments are found eight bytes at a time, with SIMD within a register, i.e., by checking the bytes
+//! of a `u64` in parallel with integer arithmetic, and line ends are found with [`memchr`]. Segments that are plain
+//! ASCII, which is most of them in English text, are tokenized byte by byte and converted to lower case on the fly.
+//! When a segment has other characters, the rest of the line is tokenized one character at a time, still straight
+//! from the input and converting each word to lower case on the fly. Only lines where converting single characters to
+//! lower case is not the same as converting the whole line, i.e., lines with a capital sigma, whose lower case depends
+//! on the next character, or with characters like 'İ' whose lower case is not a letter, are converted to lower case
+//! and given to a [`Tokenizer`]. So the tokens are always exactly the ones that [`Tokenizer`] gives for the input
+//! converted to lower case.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::mapped::ByteTokenizer;
+//! use word_frequency::tokenizer::Token;
+//!
+//! let tokens: Vec<Token> = ByteTokenizer::new("Über 42 Cats!".as_bytes()).unwrap().collect();
+//! assert_eq!(
+//!     tokens,
+//!     [
+//!         Token::Word("über".to_string()),
+//!         Token::Whitespace,
+//!         Token::Number(42),
+//!         Token::Whitespace,
+//!         Token::Word("cats".to_string()),
+//!         Token::Punctuation('!'),
+//!     ]
+//! );
+//! ```
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+
+#[cfg(not(target_arch = "wasm32"))]
+use memmap2::Mmap;
+
+use crate::error::{Error, Result};
+use crate::tokenizer::{Lexeme, Token, Tokenizer};
+
+/// The input that a [`ByteTokenizer`] works on by default, which is a vector in WebAssembly, where files cannot be
+/// mapped.
+#[cfg(not(target_arch = "wasm32"))]
+type Bytes = Mmap;
+#[cfg(target_arch = "wasm32")]
+type Bytes = Vec<u8>;
+
+/// Maps the given file into memory, or gives `None` if it cannot be mapped, e.g., because it is a pipe.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn map_file(file: &File) -> Option<Mmap> {
+    // SAFETY: the mapping is only read, and we accept that the results are garbled, like they would be when reading
+    // the file the usual way, if another process truncates or changes the file while it is counted.
+    unsafe { Mmap::map(file) }.ok()
+}
+
+/// Tokenizes UTF-8 bytes, e.g., a file mapped with [`map_file()`], giving the same tokens as a [`Tokenizer`] for the
+/// input converted to lower case.
+#[derive(Debug)]
+pub struct ByteTokenizer<B: AsRef<[u8]> = Bytes> {
+    input: B,
+    position: usize,
+    /// The end of the plain ASCII segment that `position` is in, if any
+    segment_end: usize,
+    /// The end of the line that `position` is in, if it has characters other than ASCII and is tokenized one
+    /// character at a time
+    line_end: usize,
+    /// The tokenizer for the rest of the current line, when it has characters that cannot be converted to lower case
+    /// one at a time
+    fallback: Option<Tokenizer>,
+}
+
+impl<B: AsRef<[u8]>> ByteTokenizer<B> {
+    /// Creates a new `ByteTokenizer` for the given input.
+    ///
+    /// Gives an [`Error::Decode`] if the input is not valid UTF-8.
+    pub fn new(input: B) -> Result<Self> {
+        std::str::from_utf8(input.as_ref()).map_err(|e| Error::Decode {
+            path: None,
+            message: format!("invalid UTF-8: {}", e),
+        })?;
+        Ok(Self {
+            input,
+            position: 0,
+            segment_end: 0,
+            line_end: 0,
+            fallback: None,
+        })
+    }
+
+    /// Gives the length of the input in bytes.
+    pub fn len(&self) -> usize {
+        self.input.as_ref().len()
+    }
+
+    /// Tells if the input is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn next_token(&mut self) -> Option<Token> {
+        if let Some(token) = self.fallback.as_mut().and_then(Tokenizer::next_token) {
+            return Some(token);
+        }
+        self.fallback = None;
+        match self.advance()? {
+            Next::Fallback => self.next_token(),
+            Next::Single(b) if (b as char).is_whitespace() => Some(Token::Whitespace),
+            Next::Single(b) => Some(Token::Punctuation(b as char)),
+            Next::Ascii(start) => {
+                let lexeme = self.skip_ascii_token();
+                let text = std::str::from_utf8(&self.input.as_ref()[start..self.position]).expect("ASCII");
+                Some(match (lexeme, text) {
+                    (Lexeme::Word, _) => Token::Word(text.to_ascii_lowercase()),
+                    (_, "'") => Token::Punctuation('\''),
+                    _ => number_token(text.to_string()),
+                })
+            }
+            Next::Char => {
+                let mut word = String::new();
+                Some(match self.read_char_token(&mut word) {
+                    CharToken::Whitespace => Token::Whitespace,
+                    CharToken::Word => Token::Word(word),
+                    CharToken::Number(start) => {
+                        let text = std::str::from_utf8(&self.input.as_ref()[start..self.position]).expect("UTF-8");
+                        number_token(text.to_lowercase())
+                    }
+                    CharToken::Punctuation(c) => Token::Punctuation(c),
+                })
+            }
+        }
+    }
+
+    /// Reads the next token like [`Self::next_token()`], but only tells its kind, and replaces the contents of the
+    /// given buffer with the text if it is a word, see [`Tokenizer::read_into()`].
+    pub(crate) fn read_into(&mut self, word: &mut String) -> Option<Lexeme> {
+        if let Some(lexeme) = self.fallback.as_mut().and_then(|tokenizer| tokenizer.read_into(word)) {
+            return Some(lexeme);
+        }
+        self.fallback = None;
+        match self.advance()? {
+            Next::Fallback => self.read_into(word),
+            Next::Single(b) if (b as char).is_whitespace() => Some(Lexeme::Whitespace),
+            Next::Single(_) => Some(Lexeme::Other),
+            Next::Ascii(start) => {
+                let lexeme = self.skip_ascii_token();
+                if lexeme == Lexeme::Word {
+                    word.clear();
+                    word.push_str(std::str::from_utf8(&self.input.as_ref()[start..self.position]).expect("ASCII"));
+                    word.make_ascii_lowercase();
+                }
+                Some(lexeme)
+            }
+            Next::Char => Some(match self.read_char_token(word) {
+                CharToken::Whitespace => Lexeme::Whitespace,
+                CharToken::Word => Lexeme::Word,
+                CharToken::Number(_) | CharToken::Punctuation(_) => Lexeme::Other,
+            }),
+        }
+    }
+
+    /// Finds where the next token is read from, moving past it if it is a single character outside of a segment.
+    fn advance(&mut self) -> Option<Next> {
+        let input = self.input.as_ref();
+        let b = *input.get(self.position)?;
+        if self.position < self.segment_end {
+            return Some(Next::Ascii(self.position));
+        }
+        if self.position < self.line_end {
+            return Some(Next::Char);
+        }
+        if !is_segment_byte(b) {
+            self.position += 1;
+            return Some(Next::Single(b));
+        }
+        let start = self.position;
+        let (end, is_ascii) = scan_segment(input, start);
+        if is_ascii {
+            self.segment_end = end;
+            return Some(Next::Ascii(start));
+        }
+        // Take the rest of the line, since text with some characters other than ASCII usually has more
+        let end = memchr::memchr(b'\n', &input[end..]).map_or(input.len(), |i| end + i);
+        // The line ends at an ASCII character, so it is valid UTF-8 since the whole input is
+        let line = std::str::from_utf8(&input[start..end]).expect("line of valid UTF-8");
+        if line.chars().all(|c| c.is_ascii() || lowercases_alone(c)) {
+            self.line_end = end;
+            return Some(Next::Char);
+        }
+        self.position = end;
+        self.fallback = Some(Tokenizer::new(&line.to_lowercase()));
+        Some(Next::Fallback)
+    }
+
+    /// Gives the character at the given position, if it is before the end of the current line.
+    fn char_at(&self, position: usize) -> Option<char> {
+        if position >= self.line_end {
+            return None;
+        }
+        let input = self.input.as_ref();
+        // The input is valid UTF-8, so the bytes of the character only need to be put together
+        let continuation = |i: usize| (input[position + i] & 0x3f) as u32;
+        let b = input[position] as u32;
+        let code = match b {
+            0x00..=0x7f => b,
+            0xc0..=0xdf => ((b & 0x1f) << 6) | continuation(1),
+            0xe0..=0xef => ((b & 0x0f) << 12) | (continuation(1) << 6) | continuation(2),
+            _ => ((b & 0x07) << 18) | (continuation(1) << 12) | (continuation(2) << 6) | continuation(3),
+        };
+        char::from_u32(code)
+    }
+
+    /// Moves past the next token of a line that is tokenized one character at a time, like [`Tokenizer`] would for the
+    /// line converted to lower case, replacing the contents of the given buffer with the word in lower case if it is
+    /// a word.
+    fn read_char_token(&mut self, word: &mut String) -> CharToken {
+        let start = self.position;
+        let c = self.char_at(start).expect("a character before the end of the line");
+        self.position += c.len_utf8();
+        if c.is_whitespace() {
+            CharToken::Whitespace
+        } else if c.is_alphabetic() {
+            word.clear();
+            word.extend(c.to_lowercase());
+            while let Some(c) = self.char_at(self.position) {
+                if c.is_ascii_alphabetic() {
+                    word.push(c.to_ascii_lowercase());
+                    self.position += 1;
+                } else if !c.is_ascii() && c.is_alphabetic() {
+                    word.extend(c.to_lowercase());
+                    self.position += c.len_utf8();
+                } else if cfg!(feature = "count-apostrophed-words-as-one")
+                    && c == '\''
+                    && self.char_at(self.position + 1).is_some_and(char::is_alphabetic)
+                {
+                    // The letter after the apostrophe is added by the next round
+                    word.push('\'');
+                    self.position += 1;
+                } else {
+                    break;
+                }
+            }
+            CharToken::Word
+        } else if c.is_numeric() {
+            while let Some(c) = self.char_at(self.position).filter(|c| c.is_numeric()) {
+                self.position += c.len_utf8();
+            }
+            CharToken::Number(start)
+        } else {
+            CharToken::Punctuation(c.to_lowercase().next().expect("a lower case character"))
+        }
+    }
+
+    /// Moves past the next token of a plain ASCII segment, which consists of letters, digits and apostrophes only, and
+    /// tells if it is a word. Anything else is a number or an apostrophe.
+    fn skip_ascii_token(&mut self) -> Lexeme {
+        let input = self.input.as_ref();
+        let b = input[self.position];
+        if b.is_ascii_alphabetic() {
+            while self.position < self.segment_end {
+                let b = input[self.position];
+                if b.is_ascii_alphabetic() {
+                    self.position += 1;
+                } else if b == b'\'' && input.get(self.position + 1).is_some_and(u8::is_ascii_alphabetic) {
+                    self.position += 2;
+                } else {
+                    break;
+                }
+            }
+            Lexeme::Word
+        } else if b.is_ascii_digit() {
+            while self.position < self.segment_end && input[self.position].is_ascii_digit() {
+                self.position += 1;
+            }
+            Lexeme::Other
+        } else {
+            self.position += 1;
+            Lexeme::Other
+        }
+    }
+}
+
+/// Where the next token of a [`ByteTokenizer`] is read from.
+enum Next {
+    /// The tokenizer for the rest of the line
+    Fallback,
+    /// The line that is tokenized one character at a time
+    Char,
+    /// The given ASCII character, which is whitespace or punctuation
+    Single(u8),
+    /// The plain ASCII segment, starting at the given position
+    Ascii(usize),
+}
+
+/// A token of a line that is tokenized one character at a time.
+enum CharToken {
+    Whitespace,
+    Word,
+    /// A number or numeral, starting at the given position
+    Number(usize),
+    Punctuation(char),
+}
+
+/// Gives a number token for the given digits, or a numeral if they are not an `i64`.
+fn number_token(text: String) -> Token {
+    match text.parse::<i64>() {
+        Ok(number) => Token::Number(number),
+        Err(_) => Token::Numeral(text),
+    }
+}
+
+/// The kinds of characters that [`Tokenizer`] tells apart, in the order it checks them.
+#[derive(PartialEq, Eq)]
+enum CharClass {
+    Whitespace,
+    Alphabetic,
+    Numeric,
+    Other,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_whitespace() {
+            CharClass::Whitespace
+        } else if c.is_alphabetic() {
+            CharClass::Alphabetic
+        } else if c.is_numeric() {
+            CharClass::Numeric
+        } else {
+            CharClass::Other
+        }
+    }
+}
+
+/// Tells if converting the given character to lower case on its own gives the same tokens as converting the whole
+/// line, i.e., if it does not depend on the next character, like for a capital sigma, and gives characters of the
+/// same kind, which is a single character unless it is a letter.
+fn lowercases_alone(c: char) -> bool {
+    if c == 'Σ' {
+        return false;
+    }
+    let mut lower = c.to_lowercase();
+    // Most characters, e.g., all that are already in lower case, are their own lower case
+    if lower.len() == 1 && lower.clone().next() == Some(c) {
+        return true;
+    }
+    let class = CharClass::of(c);
+    if class == CharClass::Alphabetic {
+        lower.all(|l| CharClass::of(l) == CharClass::Alphabetic)
+    } else {
+        lower.next().is_some_and(|l| CharClass::of(l) == class) && lower.next().is_none()
+    }
+}
+
+/// Gives the end of the segment starting at the given position, see [`is_segment_byte()`], and tells if it is plain
+/// ASCII. Whole chunks of eight bytes are checked at once with [`segment_lanes()`].
+fn scan_segment(input: &[u8], start: usize) -> (usize, bool) {
+    let mut end = start;
+    let mut non_ascii = 0;
+    while let Some(chunk) = input.get(end..end + 8) {
+        let chunk = u64::from_le_bytes(chunk.try_into().expect("eight bytes"));
+        if segment_lanes(chunk) != HIGH_BITS {
+            break;
+        }
+        non_ascii |= chunk & HIGH_BITS;
+        end += 8;
+    }
+    let mut is_ascii = non_ascii == 0;
+    while end < input.len() && is_segment_byte(input[end]) {
+        is_ascii &= input[end].is_ascii();
+        end += 1;
+    }
+    (end, is_ascii)
+}
+
+/// The lowest bit of each byte of a `u64`.
+const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+/// The highest bit of each byte of a `u64`.
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Gives the highest bit of each byte of the given chunk that is a segment byte, see [`is_segment_byte()`].
+fn segment_lanes(chunk: u64) -> u64 {
+    let lanes = (chunk & HIGH_BITS)
+        | bytes_between(chunk, b'0' - 1, b'9' + 1)
+        | bytes_between(chunk, b'A' - 1, b'Z' + 1)
+        | bytes_between(chunk, b'a' - 1, b'z' + 1);
+    if cfg!(feature = "count-apostrophed-words-as-one") {
+        lanes | bytes_between(chunk, b'\'' - 1, b'\'' + 1)
+    } else {
+        lanes
+    }
+}
+
+/// Gives the highest bit of each byte of the given chunk that is an ASCII character strictly between `low` and
+/// `high`, which must be at most 127 and 128. No byte carries into the next, so every byte is checked exactly, see
+/// "Determine if a word has a byte between m and n" in Sean Eron Anderson's Bit Twiddling Hacks.
+fn bytes_between(chunk: u64, low: u8, high: u8) -> u64 {
+    let seven_bits = chunk & (LOW_BITS * 127);
+    (LOW_BITS * (127 + high as u64) - seven_bits) & !chunk & (seven_bits + LOW_BITS * (127 - low as u64)) & HIGH_BITS
+}
+
+impl<B: AsRef<[u8]>> Iterator for ByteTokenizer<B> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.next_token()
+    }
+}
+
+/// Tells if a byte can be part of a word or a number, so that segments never split a token. Bytes of characters
+/// other than ASCII are always included, since they may be letters or digits.
+pub(crate) fn is_segment_byte(b: u8) -> bool {
+    !b.is_ascii() || b.is_ascii_alphanumeric() || (cfg!(feature = "count-apostrophed-words-as-one") && b == b'\'')
+}
+
+/// Gives the position after the last character of the input that always ends a token and separates the text before
+/// it from the text after it when converting to lower case, so that the input can be tokenized in two pieces split
+/// there. These are whitespace and the punctuation that is not case-ignorable, see [`separates_sigmas()`]. Characters
+/// that are cut off at the end of the input are skipped.
+#[cfg(feature = "async")]
+pub(crate) fn last_token_end(input: &[u8]) -> Option<usize> {
+    for (i, &b) in input.iter().enumerate().rev() {
+        // Only check the characters that may end a token, starting at their first byte
+        if b.is_ascii() && is_segment_byte(b) || (0x80..0xC0).contains(&b) {
+            continue;
+        }
+        let length = if b.is_ascii() { 1 } else { b.leading_ones() as usize };
+        // The character is cut off if it is not valid UTF-8
+        let c = input.get(i..i + length).and_then(|bytes| std::str::from_utf8(bytes).ok()?.chars().next());
+        let ends_token = |c: char| match CharClass::of(c) {
+            CharClass::Whitespace => true,
+            CharClass::Other => lowercases_alone(c) && separates_sigmas(c),
+            CharClass::Alphabetic | CharClass::Numeric => false,
+        };
+        if c.is_some_and(ends_token) {
+            return Some(i + length);
+        }
+    }
+    None
+}
+
+/// Tells if a character that is not a letter is neither cased nor case-ignorable, like a comma, as opposed to, e.g., a
+/// period or an apostrophe. Whether a capital sigma is at the end of a word, and becomes 'ς' instead of 'σ', looks
+/// past case-ignorable characters, so only the other ones separate the text before and after them. The standard
+/// library does not tell which characters are case-ignorable, so this converts a sigma after the character instead.
+#[cfg(feature = "async")]
+fn separates_sigmas(c: char) -> bool {
+    let mut text = String::from("Α");
+    text.push(c);
+    text.push('Σ');
+    text.to_lowercase().ends_with('σ')
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn assert_same_tokens(input: &str) {
+        let expected: Vec<Token> = Tokenizer::new(&input.to_lowercase()).collect();
+        let actual: Vec<Token> = ByteTokenizer::new(input.as_bytes()).unwrap().collect();
+        assert_eq!(actual, expected, "tokens of {:?}", input);
+
+        let mut tokenizer = ByteTokenizer::new(input.as_bytes()).unwrap();
+        let mut word = String::new();
+        for token in expected {
+            let lexeme = tokenizer.read_into(&mut word);
+            match token {
+                Token::Word(expected_word) => {
+                    assert_eq!((lexeme, word.as_str()), (Some(Lexeme::Word), expected_word.as_str()))
+                }
+                Token::Whitespace => assert_eq!(lexeme, Some(Lexeme::Whitespace)),
+                _ => assert_eq!(lexeme, Some(Lexeme::Other)),
+            }
+        }
+        assert_eq!(tokenizer.read_into(&mut word), None);
+    }
+
+    #[test]
+    fn test_same_tokens_as_tokenizer() {
+        assert_same_tokens("");
+        assert_same_tokens("It's a Test, 42 times 99999999999999999999!\r\n\x0b\x00");
+        assert_same_tokens("Ünïcödé wörds and ½ and ١٢٣ and 1½ and café's — “quoted”");
+        assert_same_tokens("İstanbul \u{212a}elvin ΣΑΣ");
+        assert_same_tokens("would've friggin' 'test'");
+    }
+
+    #[test]
+    fn test_lines_tokenized_by_character() {
+        assert_same_tokens("Ünïcödé Wörds, ½ and 1½ and Ⅻ and ١٢٣ — “Quoted”\nÀ l'École");
+        assert_same_tokens("ΣΑΣ and ΌΣΟΣ\nnext Line");
+        assert_same_tokens("Łódź 2024 ⅧⅨ ﬁne Ǆemal");
+        assert!(lowercases_alone('Ä'));
+        assert!(!lowercases_alone('Σ'));
+        assert!(!lowercases_alone('İ'));
+    }
+
+    #[test]
+    fn test_segment_lanes() {
+        for b in 0..=u8::MAX {
+            for lane in 0..8 {
+                let chunk = u64::from_le_bytes(std::array::from_fn(|i| if i == lane { b } else { b' ' }));
+                let expected = if is_segment_byte(b) { 0x80 << (lane * 8) } else { 0 };
+                assert_eq!(segment_lanes(chunk), expected, "byte {:#x} in lane {}", b, lane);
+            }
+        }
+        let text = "a1Zz9é'ab cd\n\u{ff}".as_bytes();
+        for start in 0..text.len() {
+            let end = (start..text.len()).find(|&i| !is_segment_byte(text[i])).unwrap_or(text.len());
+            let is_ascii = text[start..end].is_ascii();
+            assert_eq!(scan_segment(text, start), (end, is_ascii), "segment at {}", start);
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        assert!(matches!(ByteTokenizer::new(&b"caf\xe9"[..]), Err(Error::Decode { .. })));
+    }
+
+    #[test]
+    fn test_map_file() {
+        let file = File::open("poem.txt").unwrap();
+        let tokenizer = ByteTokenizer::new(map_file(&file).unwrap()).unwrap();
+        assert_eq!(tokenizer.len(), std::fs::metadata("poem.txt").unwrap().len() as usize);
+        let text = std::fs::read_to_string("poem.txt").unwrap();
+        assert!(tokenizer.eq(Tokenizer::new(&text.to_lowercase())));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_same_tokens_as_tokenizer(input in "[a-zA-Z0-9' .,\n]*|\\PC*|.*") {
+            let expected: Vec<Token> = Tokenizer::new(&input.to_lowercase()).collect();
+            let actual: Vec<Token> = ByteTokenizer::new(input.as_bytes()).unwrap().collect();
+            prop_assert_eq!(actual, expected);
+        }
+    }
+}
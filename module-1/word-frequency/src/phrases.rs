@@ -0,0 +1,263 @@
+//!
+//! Contains code to count multi-word expressions, like "new york" or "lady catherine", as single terms.
+//!
+//! The expressions are given as a dictionary, and are recognized in the sequence of words after tokenizing, so they
+//! match regardless of the whitespace between the words, but not across punctuation or numbers, like n-grams. Each
+//! word of the dictionary gets an ID in a [`Vocabulary`], and each expression becomes a pattern of the IDs of its
+//! words, encoded as characters. The words of the input are encoded the same way, with all words that are not in the
+//! dictionary encoded as one character that is in no pattern, and an Aho–Corasick automaton finds all expressions in
+//! a single pass over the encoded words. UTF-8 is self-synchronizing, so a pattern can only match whole characters,
+//! i.e., whole words. When expressions overlap, the one that starts first is chosen, and the longest of those.
+//!
+//! Expressions are matched before the stop words are removed, so "lady of the lake" can be an expression even though
+//! "of" and "the" are stop words, and a matched expression is never a stop word.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::frequency::FrequencyCounter;
+//! use word_frequency::phrases::Phrases;
+//!
+//! let mut frequency_counter = FrequencyCounter::new("new york, new  york and new jersey. york");
+//! frequency_counter.set_phrases(Phrases::new(["New York", "new jersey"]).unwrap());
+//! let word_frequencies = frequency_counter.count_frequencies();
+//! assert_eq!(word_frequencies["new york"], 2);
+//! assert_eq!(word_frequencies["new jersey"], 1);
+//! assert_eq!(word_frequencies["york"], 1);
+//! assert!(!word_frequencies.contains_key("new"));
+//! ```
+
+use std::sync::Arc;
+
+use aho_corasick::{AhoCorasick, MatchKind};
+
+use crate::error::{Error, Result};
+use crate::tokenizer::{Token, Tokenizer};
+use crate::vocabulary::{Vocabulary, WordId};
+
+/// The character that encodes the words that are not in any expression.
+const OTHER_WORD: char = '\0';
+/// The number of characters that are not [`OTHER_WORD`], which limits the number of distinct words in the
+/// expressions.
+const MAX_WORDS: usize = char::MAX as usize - (0xE000 - 0xD800);
+
+/// A dictionary of multi-word expressions, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Phrases {
+    /// The words of the expressions
+    vocabulary: Vocabulary,
+    /// Finds the expressions, with the words encoded by [`encode()`]
+    automaton: AhoCorasick,
+    /// The expressions, with their words joined with single spaces, indexed by the pattern IDs of the automaton
+    phrases: Vec<String>,
+}
+
+impl Phrases {
+    /// Creates a dictionary of the given expressions, which are converted to lower case to match the input.
+    ///
+    /// Gives an [`Error::Config`] if an expression is not a sequence of at least two words, e.g., because it contains
+    /// punctuation or numbers, which never occur within the expressions that are matched, or if the expressions have
+    /// more than a million distinct words.
+    pub fn new<I, S>(phrases: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut vocabulary = Vocabulary::new();
+        let mut patterns = Vec::new();
+        let mut texts = Vec::new();
+        for phrase in phrases {
+            let phrase = phrase.as_ref();
+            let words = phrase_words(&phrase.to_lowercase())
+                .ok_or_else(|| Error::Config(format!("'{}' is not a phrase of two or more words", phrase.trim())))?;
+            let ids: Vec<WordId> = words.iter().map(|word| vocabulary.intern(word)).collect();
+            if vocabulary.len() > MAX_WORDS {
+                return Err(Error::Config(format!("the phrases have more than {} distinct words", MAX_WORDS)));
+            }
+            patterns.push(ids.into_iter().map(|id| encode(Some(id))).collect::<String>());
+            texts.push(words.join(" "));
+        }
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(&patterns)
+            .map_err(|e| Error::Config(format!("too many phrases: {}", e)))?;
+        Ok(Self {
+            vocabulary,
+            automaton,
+            phrases: texts,
+        })
+    }
+
+    /// Reads a file with one expression per line. Empty lines are skipped.
+    ///
+    /// Gives an [`Error::Config`] with the line number if an expression is invalid, see [`Self::new()`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read(file_name: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(file_name).map_err(|e| Error::reading(e).with_path(file_name))?;
+        let lines: Vec<(usize, &str)> =
+            contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).collect();
+        for &(line_number, line) in &lines {
+            if phrase_words(&line.to_lowercase()).is_none() {
+                return Err(Error::Config(format!(
+                    "'{}' on line {} of {} is not a phrase of two or more words",
+                    line.trim(),
+                    line_number + 1,
+                    file_name
+                )));
+            }
+        }
+        Self::new(lines.into_iter().map(|(_, line)| line))
+    }
+
+    /// Gives the number of expressions.
+    pub fn len(&self) -> usize {
+        self.phrases.len()
+    }
+
+    /// Tells if there are no expressions.
+    pub fn is_empty(&self) -> bool {
+        self.phrases.is_empty()
+    }
+
+    /// Gives the expressions, with their words joined with single spaces.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.phrases.iter().map(String::as_str)
+    }
+}
+
+/// Gives the words of the given expression, or `None` if it contains anything but words and whitespace, or fewer
+/// than two words.
+fn phrase_words(phrase: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    for token in Tokenizer::new(phrase) {
+        match token {
+            Token::Word(word) => words.push(word),
+            Token::Whitespace => {}
+            Token::Number(_) | Token::Numeral(_) | Token::Punctuation(_) => return None,
+        }
+    }
+    (words.len() >= 2).then_some(words)
+}
+
+/// Encodes the word with the given ID as a character, or as [`OTHER_WORD`] if it is not in the dictionary. The IDs
+/// are shifted past `OTHER_WORD` and the surrogates, which are not characters.
+fn encode(id: Option<WordId>) -> char {
+    const SURROGATES: u32 = 0xE000 - 0xD800;
+    let Some(id) = id else {
+        return OTHER_WORD;
+    };
+    let code = id.index() as u32 + 1;
+    let code = if code < 0xD800 { code } else { code + SURROGATES };
+    char::from_u32(code).expect("at most MAX_WORDS distinct words, checked by Phrases::new()")
+}
+
+/// Collects the words between two pieces of punctuation or numbers, and replaces the expressions among them with
+/// single terms.
+#[derive(Debug)]
+pub(crate) struct PhraseMatcher {
+    phrases: Arc<Phrases>,
+    words: Vec<String>,
+    /// The encoded words
+    symbols: String,
+    /// The position of each word in `symbols`
+    offsets: Vec<usize>,
+}
+
+impl PhraseMatcher {
+    pub(crate) fn new(phrases: Arc<Phrases>) -> Self {
+        Self {
+            phrases,
+            words: Vec::new(),
+            symbols: String::new(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Adds the next word.
+    pub(crate) fn push(&mut self, word: &str) {
+        self.offsets.push(self.symbols.len());
+        self.symbols.push(encode(self.phrases.vocabulary.get(word)));
+        self.words.push(word.to_string());
+    }
+
+    /// Gives the words added since the last call, in order, with each expression among them given as one term, and
+    /// starts over.
+    pub(crate) fn drain(&mut self, mut emit: impl FnMut(&str)) {
+        let mut next = 0;
+        for found in self.phrases.automaton.find_iter(&self.symbols) {
+            let start = self.offsets.partition_point(|&offset| offset < found.start());
+            let end = self.offsets.partition_point(|&offset| offset < found.end());
+            self.words[next..start].iter().for_each(|word| emit(word));
+            emit(&self.phrases.phrases[found.pattern().as_usize()]);
+            next = end;
+        }
+        self.words[next..].iter().for_each(|word| emit(word));
+        self.words.clear();
+        self.symbols.clear();
+        self.offsets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(phrases: &Phrases, text: &str) -> Vec<String> {
+        let mut matcher = PhraseMatcher::new(Arc::new(phrases.clone()));
+        let mut terms = Vec::new();
+        text.split_whitespace().for_each(|word| matcher.push(word));
+        matcher.drain(|term| terms.push(term.to_string()));
+        terms
+    }
+
+    #[test]
+    fn test_new() {
+        let phrases = Phrases::new(["New  York", "lady catherine de bourgh"]).unwrap();
+        assert_eq!(phrases.iter().collect::<Vec<_>>(), ["new york", "lady catherine de bourgh"]);
+        assert!(matches!(Phrases::new(["york"]), Err(Error::Config(_))));
+        assert!(matches!(Phrases::new(["new york, ny"]), Err(Error::Config(_))));
+        assert!(matches!(Phrases::new(["route 66"]), Err(Error::Config(_))));
+        assert!(Phrases::new(Vec::<String>::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_matching() {
+        let phrases = Phrases::new(["new york", "new york city", "york city", "city hall"]).unwrap();
+        assert_eq!(terms(&phrases, "in new york city hall"), ["in", "new york city", "hall"]);
+        assert_eq!(terms(&phrases, "new new york york"), ["new", "new york", "york"]);
+        assert_eq!(terms(&phrases, "york city hall"), ["york city", "hall"]);
+        assert_eq!(terms(&phrases, "newyork city"), ["newyork", "city"]);
+        assert!(terms(&phrases, "").is_empty());
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(None), OTHER_WORD);
+        let mut vocabulary = Vocabulary::new();
+        let ids: Vec<WordId> = (0..0xE000).map(|i| vocabulary.intern(&i.to_string())).collect();
+        assert_eq!(encode(Some(ids[0])), '\u{1}');
+        assert_eq!(encode(Some(ids[0xD7FE])), '\u{D7FF}');
+        assert_eq!(encode(Some(ids[0xD7FF])), '\u{E000}');
+    }
+
+    #[test]
+    fn test_too_many_words() {
+        // Words of letters only, since digits would make numbers
+        let word = |i: usize| format!("{:x}", i).chars().map(|c| (c as u8 + 17) as char).collect::<String>();
+        let phrases = (0..=MAX_WORDS / 2).map(|i| format!("{} {}", word(2 * i), word(2 * i + 1)));
+        let error = Phrases::new(phrases).unwrap_err();
+        assert!(matches!(error, Error::Config(_)), "{}", error);
+    }
+
+    #[test]
+    fn test_read() {
+        let path = std::env::temp_dir().join(format!("word-frequency-phrases-{}.txt", std::process::id()));
+        std::fs::write(&path, "new york\n\nLady Catherine\nbad!\n").unwrap();
+        let error = Phrases::read(path.to_str().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("line 4"), "{}", error);
+        std::fs::write(&path, "new york\n\nLady Catherine\n").unwrap();
+        assert_eq!(Phrases::read(path.to_str().unwrap()).unwrap().len(), 2);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(Phrases::read("no-such-file.txt"), Err(Error::Io { .. })));
+    }
+}
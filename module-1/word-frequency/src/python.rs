@@ -0,0 +1,176 @@
+//!
+//! Contains the Python bindings of the library, built as the `word_frequency` extension module with maturin, see
+//! `pyproject.toml`. Needs the `python` feature.
+//!
+//! The module has the classes `Tokenizer` and `FrequencyCounter` and the function `sort_frequencies`:
+//!
+//! ```python
+//! import word_frequency
+//!
+//! list(word_frequency.Tokenizer("Hi, Bob"))
+//! # [("word", "Hi"), ("punctuation", ","), ("whitespace", None), ("word", "Bob")]
+//!
+//! counter = word_frequency.FrequencyCounter.from_file("pride-and-prejudice.txt")
+//! counter.read_stop_words("stop-words.txt")
+//! frequencies = counter.count_frequencies()
+//! word_frequency.sort_frequencies(frequencies)[:2]
+//! # [("mr", 786), ("elizabeth", 635)]
+//! word_frequency.sort_frequencies(frequencies, dicts=True)[0]
+//! # {"word": "mr", "count": 786, "share": 0.0152..., "relative": 1.0}
+//! ```
+//!
+//! Errors are raised as `OSError` if a file cannot be read, and as `ValueError` otherwise.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyTuple};
+
+use crate::error::Error;
+use crate::frequency::FrequencyCounter;
+use crate::tokenizer::{Token, Tokenizer};
+
+impl From<Error> for PyErr {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io { .. } => PyOSError::new_err(error.to_string()),
+            Error::Decode { .. } | Error::StopWords { .. } | Error::Config(_) => {
+                PyValueError::new_err(error.to_string())
+            }
+        }
+    }
+}
+
+/// Breaks a string into tokens, which are tuples of a kind and a value: `("word", str)`, `("number", int)`,
+/// `("numeral", str)`, `("whitespace", None)` or `("punctuation", str)`.
+#[pyclass(name = "Tokenizer", module = "word_frequency")]
+struct PyTokenizer {
+    tokenizer: Tokenizer,
+}
+
+#[pymethods]
+impl PyTokenizer {
+    #[new]
+    fn new(text: &str) -> Self {
+        Self {
+            tokenizer: Tokenizer::new(text),
+        }
+    }
+
+    /// The position of the next token, as the number of characters before it.
+    #[getter]
+    fn position(&self) -> usize {
+        self.tokenizer.position()
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyTuple>>> {
+        self.tokenizer.next_token().map(|token| token_tuple(py, token)).transpose()
+    }
+}
+
+fn token_tuple<'py>(py: Python<'py>, token: Token) -> PyResult<Bound<'py, PyTuple>> {
+    Ok(match token {
+        Token::Word(word) => ("word", word).into_pyobject(py)?,
+        Token::Number(number) => ("number", number).into_pyobject(py)?,
+        Token::Numeral(numeral) => ("numeral", numeral).into_pyobject(py)?,
+        Token::Whitespace => ("whitespace", py.None()).into_pyobject(py)?,
+        Token::Punctuation(c) => ("punctuation", c).into_pyobject(py)?,
+    })
+}
+
+/// Counts the number of occurrences of words in a text, ignoring the stop words.
+#[pyclass(name = "FrequencyCounter", module = "word_frequency")]
+struct PyFrequencyCounter {
+    frequency_counter: FrequencyCounter,
+}
+
+#[pymethods]
+impl PyFrequencyCounter {
+    /// Creates a counter for the given text, which is counted as it is, without converting it to lower case.
+    #[new]
+    #[pyo3(signature = (text = ""))]
+    fn new(text: &str) -> Self {
+        Self {
+            frequency_counter: FrequencyCounter::new(text),
+        }
+    }
+
+    /// Creates a counter for the text of the given file, converted to lower case. Compressed files are decompressed,
+    /// and the format is guessed from the file name extension, like on the command line.
+    #[staticmethod]
+    fn from_file(path: &str) -> PyResult<Self> {
+        Ok(Self {
+            frequency_counter: FrequencyCounter::from_file(path)?,
+        })
+    }
+
+    /// Reads a comma-separated file with stop words, replacing the current stop words.
+    fn read_stop_words(&mut self, path: &str) -> PyResult<()> {
+        Ok(self.frequency_counter.read_stop_words(path)?)
+    }
+
+    /// The stop words, which should be in lower case to match the input.
+    #[getter]
+    fn get_stop_words(&self) -> Vec<String> {
+        let mut stop_words: Vec<String> = self.frequency_counter.stop_words().iter().cloned().collect();
+        stop_words.sort();
+        stop_words
+    }
+
+    #[setter]
+    fn set_stop_words(&mut self, stop_words: Vec<String>) {
+        self.frequency_counter.set_stop_words(stop_words.into_iter().collect());
+    }
+
+    /// Counts the words that are left in the input, giving a dict from words to counts. The counting is done without
+    /// holding the GIL, so other Python threads can run meanwhile.
+    fn count_frequencies(&mut self, py: Python<'_>) -> HashMap<String, usize> {
+        py.detach(|| self.frequency_counter.count_frequencies())
+    }
+
+    /// Counts the sequences of `n` consecutive words that are left in the input, joined with single spaces.
+    fn count_ngrams(&mut self, py: Python<'_>, n: usize) -> HashMap<String, usize> {
+        py.detach(|| self.frequency_counter.count_ngrams(n))
+    }
+}
+
+/// Sorts a dict from words to counts in descending order by count, and alphabetically for words with the same count.
+/// Gives a list of `(word, count)` tuples, or of dicts with the keys `word`, `count`, `share` and `relative` if
+/// `dicts` is true, where `share` is the fraction of all counted words and `relative` the fraction of the count of
+/// the most common word.
+#[pyfunction]
+#[pyo3(signature = (frequencies, dicts = false))]
+fn sort_frequencies<'py>(
+    py: Python<'py>,
+    frequencies: HashMap<String, usize>,
+    dicts: bool,
+) -> PyResult<Bound<'py, PyList>> {
+    let sorted_frequencies = FrequencyCounter::sort_frequencies(&frequencies);
+    let list = PyList::empty(py);
+    for count in sorted_frequencies {
+        if dicts {
+            let dict = PyDict::new(py);
+            dict.set_item("word", count.word)?;
+            dict.set_item("count", count.count)?;
+            dict.set_item("share", count.share)?;
+            dict.set_item("relative", count.relative)?;
+            list.append(dict)?;
+        } else {
+            list.append((count.word, count.count))?;
+        }
+    }
+    Ok(list)
+}
+
+#[pymodule]
+fn word_frequency(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyTokenizer>()?;
+    module.add_class::<PyFrequencyCounter>()?;
+    module.add_function(wrap_pyfunction!(sort_frequencies, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,222 @@
+//!
+//! Contains code to query the sorted counts given by [`FrequencyCounter::sort_frequencies()`], i.e., to filter them
+//! by count, length or a regular expression, to sort them in another order, and to take a page of them.
+//!
+//! The shares of the counts are not changed by filtering, so they are still shares of all counted words.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::frequency::FrequencyCounter;
+//! use word_frequency::query::{Query, SortKey};
+//!
+//! let word_frequencies = FrequencyCounter::new("a cat and a hat and a bat sat").count_frequencies();
+//! let sorted_frequencies = FrequencyCounter::sort_frequencies(&word_frequencies);
+//! let query = Query::new().min_length(3).regex("at$").unwrap().sort(SortKey::Alpha).offset(1).limit(2);
+//! let words: Vec<&str> = query.apply(sorted_frequencies).iter().map(|count| count.word).collect();
+//! assert_eq!(words, ["cat", "hat"]);
+//! ```
+//!
+//! [`FrequencyCounter::sort_frequencies()`]: crate::frequency::FrequencyCounter::sort_frequencies()
+
+use clap::ValueEnum;
+use regex::Regex;
+
+use crate::collation::Collation;
+use crate::error::{Error, Result};
+use crate::frequency::Count;
+
+/// The orders that a [`Query`] can sort the counts in.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, ValueEnum)]
+pub enum SortKey {
+    /// By count, the most common first, and alphabetically for the same count
+    #[default]
+    Count,
+    /// Alphabetically
+    Alpha,
+    /// By the number of characters, the longest first, and like `count` for the same length
+    Length,
+}
+
+/// Filters, sorts and pages sorted counts. A new `Query` keeps all counts in the order given by
+/// [`Count`]'s `Ord` implementation, and each method adds a condition or changes the order.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    min_count: Option<usize>,
+    max_count: Option<usize>,
+    min_length: Option<usize>,
+    regex: Option<Regex>,
+    sort: SortKey,
+    ascending: bool,
+    collation: Collation,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl Query {
+    /// Creates a new `Query` that keeps all counts, sorted by count.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps only the words occurring at least this many times.
+    pub fn min_count(mut self, min_count: usize) -> Self {
+        self.min_count = Some(min_count);
+        self
+    }
+
+    /// Keeps only the words occurring at most this many times.
+    pub fn max_count(mut self, max_count: usize) -> Self {
+        self.max_count = Some(max_count);
+        self
+    }
+
+    /// Keeps only the words with at least this many characters.
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = Some(min_length);
+        self
+    }
+
+    /// Keeps only the words matching the given regular expression anywhere, so use `^` and `$` to match whole words.
+    ///
+    /// Gives an [`Error::Config`] if the regular expression is invalid.
+    pub fn regex(mut self, regex: &str) -> Result<Self> {
+        let compiled =
+            Regex::new(regex).map_err(|e| Error::Config(format!("invalid regular expression '{}': {}", regex, e)))?;
+        self.regex = Some(compiled);
+        Ok(self)
+    }
+
+    /// Sorts the counts in the given order.
+    pub fn sort(mut self, sort: SortKey) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Sorts the counts in ascending order instead, i.e., the least common or the shortest words first. Alphabetical
+    /// order is always ascending.
+    pub fn ascending(mut self) -> Self {
+        self.ascending = true;
+        self
+    }
+
+    /// Compares words with the given collation when sorting alphabetically and for words with the same count, instead
+    /// of by their bytes.
+    pub fn collation(mut self, collation: Collation) -> Self {
+        self.collation = collation;
+        self
+    }
+
+    /// Skips this many counts after filtering and sorting, e.g., to get the next page.
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Gives at most this many counts.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Tells if the given count passes all filters of the query.
+    pub fn matches(&self, count: &Count<'_>) -> bool {
+        self.min_count.is_none_or(|min_count| count.count >= min_count)
+            && self.max_count.is_none_or(|max_count| count.count <= max_count)
+            && self.min_length.is_none_or(|min_length| length(count) >= min_length)
+            && self.regex.as_ref().is_none_or(|regex| regex.is_match(count.word))
+    }
+
+    /// Gives the counts that pass the filters, sorted and paged. The counts are usually the ones given by
+    /// [`FrequencyCounter::sort_frequencies()`], but may be in any order.
+    ///
+    /// [`FrequencyCounter::sort_frequencies()`]: crate::frequency::FrequencyCounter::sort_frequencies()
+    pub fn apply<'a>(&self, counts: Vec<Count<'a>>) -> Vec<Count<'a>> {
+        let mut counts: Vec<Count<'a>> = counts.into_iter().filter(|count| self.matches(count)).collect();
+        let collation = &self.collation;
+        match (self.sort, self.ascending) {
+            (SortKey::Count, false) => counts.sort_by(|a, b| collation.compare_counts(a, b)),
+            (SortKey::Count, true) => {
+                counts.sort_by(|a, b| a.count.cmp(&b.count).then_with(|| collation.compare(a.word, b.word)))
+            }
+            (SortKey::Alpha, _) => counts.sort_by(|a, b| collation.compare(a.word, b.word)),
+            (SortKey::Length, false) => counts.sort_by(|a, b| {
+                length(b).cmp(&length(a)).then_with(|| collation.compare_counts(a, b))
+            }),
+            (SortKey::Length, true) => {
+                counts.sort_by(|a, b| length(a).cmp(&length(b)).then_with(|| collation.compare_counts(a, b)))
+            }
+        }
+        counts.into_iter().skip(self.offset).take(self.limit.unwrap_or(usize::MAX)).collect()
+    }
+}
+
+/// Gives the length of the word of the given count in characters.
+fn length(count: &Count<'_>) -> usize {
+    count.word.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts() -> Vec<Count<'static>> {
+        let mut counts = vec![
+            Count::new("the", 5),
+            Count::new("cat", 3),
+            Count::new("hat", 3),
+            Count::new("a", 2),
+            Count::new("über", 1),
+            Count::new("elephant", 1),
+        ];
+        counts.sort();
+        counts
+    }
+
+    fn words(query: Query) -> Vec<&'static str> {
+        query.apply(counts()).iter().map(|count| count.word).collect()
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(words(Query::new()), ["the", "cat", "hat", "a", "elephant", "über"]);
+        assert_eq!(words(Query::new().min_count(3)), ["the", "cat", "hat"]);
+        assert_eq!(words(Query::new().max_count(2)), ["a", "elephant", "über"]);
+        assert_eq!(words(Query::new().min_count(2).max_count(3)), ["cat", "hat", "a"]);
+        assert_eq!(words(Query::new().min_length(4)), ["elephant", "über"]);
+        assert_eq!(words(Query::new().min_length(0)).len(), 6);
+        assert_eq!(words(Query::new().regex("^.at$").unwrap()), ["cat", "hat"]);
+        assert!(matches!(Query::new().regex("("), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_sort() {
+        assert_eq!(words(Query::new().sort(SortKey::Alpha)), ["a", "cat", "elephant", "hat", "the", "über"]);
+        assert_eq!(words(Query::new().sort(SortKey::Alpha).ascending()), words(Query::new().sort(SortKey::Alpha)));
+        assert_eq!(words(Query::new().ascending()), ["elephant", "über", "a", "cat", "hat", "the"]);
+        assert_eq!(words(Query::new().sort(SortKey::Length)), ["elephant", "über", "the", "cat", "hat", "a"]);
+        let words_by_length = words(Query::new().sort(SortKey::Length).ascending());
+        assert_eq!(words_by_length, ["a", "the", "cat", "hat", "über", "elephant"]);
+    }
+
+    #[test]
+    fn test_collation() {
+        let counts = || vec![Count::new("zebra", 1), Count::new("ähnlich", 1), Count::new("apa", 1)];
+        let words = |query: Query| -> Vec<&str> { query.apply(counts()).iter().map(|count| count.word).collect() };
+        assert_eq!(words(Query::new()), ["apa", "zebra", "ähnlich"]);
+        let german = Collation::unicode(Some("de"), false).unwrap();
+        assert_eq!(words(Query::new().collation(german.clone())), ["ähnlich", "apa", "zebra"]);
+        assert_eq!(words(Query::new().sort(SortKey::Alpha).collation(german)), ["ähnlich", "apa", "zebra"]);
+        let swedish = Collation::unicode(Some("sv"), false).unwrap();
+        assert_eq!(words(Query::new().sort(SortKey::Length).collation(swedish)), ["ähnlich", "zebra", "apa"]);
+    }
+
+    #[test]
+    fn test_paging() {
+        assert_eq!(words(Query::new().limit(2)), ["the", "cat"]);
+        assert_eq!(words(Query::new().offset(2).limit(2)), ["hat", "a"]);
+        assert_eq!(words(Query::new().offset(5).limit(2)), ["über"]);
+        assert!(words(Query::new().offset(10)).is_empty());
+        assert_eq!(words(Query::new().min_count(3).offset(1)), ["cat", "hat"]);
+    }
+}
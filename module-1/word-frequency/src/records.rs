@@ -0,0 +1,215 @@
+//!
+//! Contains code to select the text to count from structured records, i.e., a column of a CSV file or a field of
+//! the objects in a JSON Lines file, optionally grouping the records by the value of another column or field.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::records::{FieldSelector, RecordFormat};
+//!
+//! let csv = "id,author,text\n1,Emily,I'm nobody\n2,Walt,I celebrate myself\n3,Emily,Who are you\n";
+//! let selector = FieldSelector::new(RecordFormat::Csv, "text").group_by("author");
+//! let groups = selector.read_groups(Box::new(csv.as_bytes())).unwrap();
+//! assert_eq!(groups[&Some("Emily".to_string())], "I'm nobody\nWho are you\n");
+//! assert_eq!(groups[&Some("Walt".to_string())], "I celebrate myself\n");
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+
+use clap::ValueEnum;
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+/// The formats of structured records that we can select text from.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, ValueEnum)]
+pub enum RecordFormat {
+    /// Comma-separated values, with a header row naming the columns
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+/// Selects the field with the text to count from each record, and optionally the field to group the records by.
+///
+/// For CSV, a field is the name of a column in the header row. For JSON Lines, a field is a path of object keys or
+/// array indexes separated by dots, e.g., `message.body` or `replies.0.text`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FieldSelector {
+    format: RecordFormat,
+    text_field: String,
+    group_field: Option<String>,
+}
+
+/// The text of all records in the same group, joined with newlines, keyed by the value of the group field. The key
+/// is `None` if the records are not grouped, or for records where the group field is missing.
+pub type RecordGroups = BTreeMap<Option<String>, String>;
+
+impl FieldSelector {
+    /// Creates a new `FieldSelector` that selects the given text field from records in the given format, without
+    /// grouping.
+    pub fn new(format: RecordFormat, text_field: &str) -> Self {
+        Self {
+            format,
+            text_field: text_field.to_string(),
+            group_field: None,
+        }
+    }
+
+    /// Makes the `FieldSelector` group the records by the value of the given field.
+    pub fn group_by(mut self, group_field: &str) -> Self {
+        self.group_field = Some(group_field.to_string());
+        self
+    }
+
+    /// Reads all records from the given reader and collects the text of the selected field, grouped by the value of
+    /// the group field if there is one. Records where the text field is missing are skipped.
+    ///
+    /// Gives an [`Error::Config`] if a CSV column does not exist, and an [`Error::Decode`] if a record is malformed.
+    pub fn read_groups(&self, reader: Box<dyn Read>) -> Result<RecordGroups> {
+        let mut groups = RecordGroups::new();
+        let mut add = |group: Option<String>, text: &str| {
+            let group_text = groups.entry(group).or_default();
+            group_text.push_str(text);
+            group_text.push('\n');
+        };
+        match self.format {
+            RecordFormat::Csv => self.read_csv(reader, &mut add)?,
+            RecordFormat::JsonLines => self.read_json_lines(reader, &mut add)?,
+        }
+        Ok(groups)
+    }
+
+    fn read_csv(&self, reader: Box<dyn Read>, add: &mut impl FnMut(Option<String>, &str)) -> Result<()> {
+        let mut csv_reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+        let headers = csv_reader.headers().map_err(csv_error)?;
+        let text_column = find_column(headers, &self.text_field)?;
+        let group_column = match &self.group_field {
+            Some(group_field) => Some(find_column(headers, group_field)?),
+            None => None,
+        };
+        for record in csv_reader.records() {
+            let record = record.map_err(csv_error)?;
+            if let Some(text) = record.get(text_column) {
+                let group = group_column.and_then(|column| record.get(column)).map(str::to_string);
+                add(group, text);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_json_lines(&self, reader: Box<dyn Read>, add: &mut impl FnMut(Option<String>, &str)) -> Result<()> {
+        for (line_number, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(Error::reading)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Value = serde_json::from_str(&line).map_err(|e| Error::Decode {
+                path: None,
+                message: format!("line {}: {}", line_number + 1, e),
+            })?;
+            if let Some(text) = select(&record, &self.text_field).and_then(value_text) {
+                let group = self.group_field.as_ref().and_then(|field| select(&record, field)).and_then(value_text);
+                add(group, &text);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn find_column(headers: &csv::StringRecord, name: &str) -> Result<usize> {
+    headers
+        .iter()
+        .position(|header| header.trim() == name)
+        .ok_or_else(|| Error::Config(format!("no column named '{}' in the CSV header", name)))
+}
+
+fn csv_error(error: csv::Error) -> Error {
+    let message = error.to_string();
+    match error.into_kind() {
+        csv::ErrorKind::Io(source) => Error::reading(source),
+        _ => Error::Decode { path: None, message },
+    }
+}
+
+/// Follows a dot-separated path of object keys and array indexes from the given JSON value.
+fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Object(object) => object.get(key),
+        Value::Array(array) => array.get(key.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// Gives the text of a JSON value: strings as they are, numbers and booleans formatted, and the elements of arrays
+/// joined with spaces. Gives `None` for null and objects.
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Array(array) => Some(array.iter().filter_map(value_text).collect::<Vec<_>>().join(" ")),
+        Value::Null | Value::Object(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_csv_column() {
+        let csv = "id, text\n1,\"Hello, World\"\n2,\"Quoted \"\"text\"\"\nwith a newline\"\n3\n";
+        let groups = FieldSelector::new(RecordFormat::Csv, "text").read_groups(Box::new(csv.as_bytes())).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[&None], "Hello, World\nQuoted \"text\"\nwith a newline\n");
+    }
+
+    #[test]
+    fn test_read_csv_missing_column() {
+        let selector = FieldSelector::new(RecordFormat::Csv, "body");
+        let error = selector.read_groups(Box::new("id,text\n1,Hello\n".as_bytes())).unwrap_err();
+        assert!(matches!(error, Error::Config(_)));
+        let selector = FieldSelector::new(RecordFormat::Csv, "text").group_by("author");
+        assert!(selector.read_groups(Box::new("id,text\n1,Hello\n".as_bytes())).is_err());
+    }
+
+    #[test]
+    fn test_read_json_lines_field() {
+        let jsonl = r#"{"id": 1, "message": {"body": "Hello", "tags": ["a", "b"]}, "user": "ann"}
+
+{"id": 2, "message": {"body": "World"}, "user": "bob"}
+{"id": 3, "user": "ann"}
+{"id": 4, "message": {"body": "Again"}, "user": "ann"}
+{"id": 5, "message": {"body": "Anonymous"}}
+"#;
+        let selector = FieldSelector::new(RecordFormat::JsonLines, "message.body").group_by("user");
+        let groups = selector.read_groups(Box::new(jsonl.as_bytes())).unwrap();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[&None], "Anonymous\n");
+        assert_eq!(groups[&Some("ann".to_string())], "Hello\nAgain\n");
+        assert_eq!(groups[&Some("bob".to_string())], "World\n");
+
+        let selector = FieldSelector::new(RecordFormat::JsonLines, "message.tags");
+        let groups = selector.read_groups(Box::new(jsonl.as_bytes())).unwrap();
+        assert_eq!(groups[&None], "a b\n");
+    }
+
+    #[test]
+    fn test_read_json_lines_invalid_json() {
+        let selector = FieldSelector::new(RecordFormat::JsonLines, "text");
+        let error = selector.read_groups(Box::new("{\"text\": \"Hello\"}\n{text: 1}\n".as_bytes())).unwrap_err();
+        assert!(matches!(error, Error::Decode { ref message, .. } if message.starts_with("line 2:")));
+    }
+
+    #[test]
+    fn test_select() {
+        let value: Value = serde_json::from_str(r#"{"a": {"b": [{"c": "x"}, {"c": 42}]}}"#).unwrap();
+        assert_eq!(select(&value, "a.b.0.c"), Some(&Value::from("x")));
+        assert_eq!(select(&value, "a.b.1.c"), Some(&Value::from(42)));
+        assert_eq!(select(&value, "a.b.2.c"), None);
+        assert_eq!(select(&value, "a.b.c"), None);
+        assert_eq!(select(&value, "a.x"), None);
+    }
+}
@@ -0,0 +1,442 @@
+//!
+//! Contains a small HTTP server that counts the words of text sent to it and gives the most common words as JSON.
+//!
+//! The server has a single endpoint, `POST /frequencies`, taking the text either as the request body, or as one or
+//! more files uploaded as `multipart/form-data`. Compressed input is decompressed, like for the command line tool.
+//! The query parameters mirror the options of the command line tool:
+//!
+//! - `num_words`: the number of words to give, default 25
+//! - `stop_words`: ignore the stop words, default true
+//! - `ngrams`: count sequences of this many words, default 1
+//! - `format`: the input format, e.g., `html`, guessed from the file names of uploaded files if not given
+//! - `relative`: deprecated and ignored, since every count now gives the relative count as well
+//!
+//! The response is a JSON object like `{"total": 4, "frequencies": [{"word": "cats", "count": 2, "percent": 50.0,
+//! "per_million": 500000.0, "relative": 100.0}, ...]}`, where `total` is the total number of words counted, and
+//! `relative` is the count relative to the most common word, between 0 and 100. Errors give a 4xx status and a JSON
+//! object like `{"error": "..."}`.
+//!
+//! Requests are handled one at a time, in the order they arrive.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use word_frequency::server::Server;
+//!
+//! let server = Server::bind("127.0.0.1:8080", Some("stop-words.txt")).unwrap();
+//! println!("Listening on http://{}", server.local_addr());
+//! server.run().unwrap();
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use std::net::SocketAddr;
+
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::error::{Error, Result};
+use crate::frequency::{Count, FrequencyCounter};
+use crate::input_format::InputFormat;
+
+/// The largest request body that we accept, in bytes.
+const MAX_BODY_LENGTH: u64 = 64 * 1024 * 1024;
+
+/// The default number of words to give, like for the command line tool.
+const DEFAULT_NUM_WORDS: usize = 25;
+
+/// Counts the words of text sent to it over HTTP.
+pub struct Server {
+    server: tiny_http::Server,
+    stop_words: HashSet<String>,
+}
+
+/// The options for counting, given as query parameters.
+#[derive(Debug, PartialEq, Eq)]
+struct Query {
+    num_words: usize,
+    stop_words: bool,
+    ngrams: usize,
+    format: Option<InputFormat>,
+}
+
+/// A part of a `multipart/form-data` body: the file name, if any, and the contents.
+type Part = (Option<String>, Vec<u8>);
+
+/// An error to respond with, with an HTTP status code and a message.
+#[derive(Debug, PartialEq, Eq)]
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+impl Server {
+    /// Creates a new `Server` listening on the given address, e.g., `127.0.0.1:8080`, using port 0 to let the
+    /// operating system choose a free port. The stop words are read from the given file, if any.
+    pub fn bind(address: &str, stop_words_file: Option<&str>) -> Result<Self> {
+        let mut stop_words = FrequencyCounter::new("");
+        if let Some(stop_words_file) = stop_words_file {
+            stop_words.read_stop_words(stop_words_file)?;
+        }
+        let server = tiny_http::Server::http(address)
+            .map_err(|e| Error::Config(format!("cannot listen on '{}': {}", address, e)))?;
+        Ok(Self {
+            server,
+            stop_words: stop_words.stop_words().clone(),
+        })
+    }
+
+    /// Gives the address that the server listens on, which tells the port chosen if the server was bound to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.server
+            .server_addr()
+            .to_ip()
+            .expect("the server listens on an IP address")
+    }
+
+    /// Handles requests until an error occurs when receiving a request.
+    pub fn run(&self) -> Result<()> {
+        loop {
+            self.handle_next()?;
+        }
+    }
+
+    /// Waits for the next request and responds to it.
+    pub fn handle_next(&self) -> Result<()> {
+        let mut request = self.server.recv()?;
+        let (status, body) = match self.handle(&mut request) {
+            Ok(body) => (200, body),
+            Err(error) => (error.status, json!({ "error": error.message })),
+        };
+        let content_type = Header::from_bytes("Content-Type", "application/json").expect("the header is valid");
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type);
+        // The client may have gone away, which is no reason to stop the server
+        let _ = request.respond(response);
+        Ok(())
+    }
+
+    fn handle(&self, request: &mut Request) -> std::result::Result<Value, HttpError> {
+        let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+        if path != "/frequencies" {
+            return Err(HttpError::new(
+                404,
+                format!("no such endpoint '{}', use '/frequencies'", path),
+            ));
+        }
+        if *request.method() != Method::Post {
+            return Err(HttpError::new(405, "use POST to send the text to count".to_string()));
+        }
+        let query = Query::parse(query)?;
+        let content_type = header_value(request, "Content-Type").unwrap_or_default();
+        let body = read_body(request)?;
+        let inputs = match multipart_boundary(&content_type) {
+            Some(boundary) => parse_multipart(&body, &boundary)?,
+            None => vec![(None, body)],
+        };
+
+        let mut frequencies = HashMap::new();
+        for (file_name, data) in inputs {
+            let format = query
+                .format
+                .or_else(|| file_name.and_then(InputFormat::from_path))
+                .unwrap_or(InputFormat::Text);
+            let mut frequency_counter = FrequencyCounter::from_reader_with_format(Box::new(Cursor::new(data)), format)
+                .map_err(|e| HttpError::new(400, e.to_string()))?;
+            if query.stop_words {
+                frequency_counter.set_stop_words(self.stop_words.clone());
+            }
+            frequency_counter.add_ngram_frequencies(query.ngrams, &mut frequencies);
+        }
+        let sorted_frequencies = FrequencyCounter::sort_frequencies(&frequencies);
+        let counts: Vec<Value> = sorted_frequencies
+            .iter()
+            .take(query.num_words)
+            .map(Count::to_json)
+            .collect();
+        Ok(json!({ "total": frequencies.values().sum::<usize>(), "frequencies": counts }))
+    }
+}
+
+impl Query {
+    fn parse(query: &str) -> std::result::Result<Self, HttpError> {
+        let mut result = Query {
+            num_words: DEFAULT_NUM_WORDS,
+            stop_words: true,
+            ngrams: 1,
+            format: None,
+        };
+        for parameter in query.split('&').filter(|parameter| !parameter.is_empty()) {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, "true"));
+            let value = percent_decode(value);
+            let invalid = || HttpError::new(400, format!("invalid value '{}' for query parameter '{}'", value, name));
+            match name {
+                "num_words" => result.num_words = value.parse().map_err(|_| invalid())?,
+                "stop_words" => result.stop_words = parse_bool(&value).ok_or_else(invalid)?,
+                "ngrams" => result.ngrams = value.parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?,
+                "format" => result.format = Some(InputFormat::from_str(&value, true).map_err(|_| invalid())?),
+                // Still accepted for older clients, but every count includes `relative` anyway
+                "relative" => {
+                    parse_bool(&value).ok_or_else(invalid)?;
+                }
+                _ => return Err(HttpError::new(400, format!("unknown query parameter '{}'", name))),
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl HttpError {
+    fn new(status: u16, message: String) -> Self {
+        Self { status, message }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "1" | "yes" => Some(true),
+        "false" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Decodes a URL query value, where '+' is a space and '%XX' is a byte given in hexadecimal.
+fn percent_decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' if tail.len() >= 2 && tail[..2].iter().all(u8::is_ascii_hexdigit) => {
+                let hex = std::str::from_utf8(&tail[..2]).expect("hex digits are ASCII");
+                bytes.push(u8::from_str_radix(hex, 16).expect("the digits are hexadecimal"));
+                rest = &tail[2..];
+                continue;
+            }
+            _ => bytes.push(b),
+        }
+        rest = tail;
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn header_value(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.to_string())
+}
+
+fn read_body(request: &mut Request) -> std::result::Result<Vec<u8>, HttpError> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_LENGTH + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| HttpError::new(400, format!("error reading the request: {}", e)))?;
+    if body.len() as u64 > MAX_BODY_LENGTH {
+        return Err(HttpError::new(
+            413,
+            format!("the request is larger than {} bytes", MAX_BODY_LENGTH),
+        ));
+    }
+    Ok(body)
+}
+
+/// Gives the boundary of a `multipart/form-data` content type, or `None` for any other content type.
+fn multipart_boundary(content_type: &str) -> Option<String> {
+    let (media_type, parameters) = content_type.split_once(';')?;
+    if !media_type.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    parameters.split(';').find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Splits a `multipart/form-data` body into its parts.
+fn parse_multipart(body: &[u8], boundary: &str) -> std::result::Result<Vec<Part>, HttpError> {
+    let invalid = || HttpError::new(400, "malformed multipart/form-data body".to_string());
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut parts = Vec::new();
+    let mut rest = &body[find(body, &delimiter).ok_or_else(invalid)? + delimiter.len()..];
+    while !rest.starts_with(b"--") {
+        let part_start = find(rest, b"\r\n").ok_or_else(invalid)? + 2;
+        let part_length = find(&rest[part_start..], &[b"\r\n", &delimiter[..]].concat()).ok_or_else(invalid)?;
+        let part = &rest[part_start..part_start + part_length];
+        let headers_length = find(part, b"\r\n\r\n").ok_or_else(invalid)?;
+        let headers = String::from_utf8_lossy(&part[..headers_length]);
+        parts.push((
+            content_disposition_file_name(&headers),
+            part[headers_length + 4..].to_vec(),
+        ));
+        rest = &rest[part_start + part_length + 2 + delimiter.len()..];
+    }
+    Ok(parts)
+}
+
+/// Gives the file name from the `Content-Disposition` header among the headers of a multipart part.
+fn content_disposition_file_name(headers: &str) -> Option<String> {
+    let disposition = headers
+        .lines()
+        .find_map(|line| {
+            line.split_once(':')
+                .filter(|(name, _)| name.trim().eq_ignore_ascii_case("content-disposition"))
+        })?
+        .1;
+    disposition.split(';').find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        (name.trim() == "filename").then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_query_parse() {
+        assert_eq!(
+            Query::parse("").unwrap(),
+            Query {
+                num_words: 25,
+                stop_words: true,
+                ngrams: 1,
+                format: None
+            }
+        );
+        assert_eq!(
+            Query::parse("num_words=3&stop_words=false&ngrams=2&format=HTML").unwrap(),
+            Query {
+                num_words: 3,
+                stop_words: false,
+                ngrams: 2,
+                format: Some(InputFormat::Html)
+            }
+        );
+        assert_eq!(Query::parse("num_words=x").unwrap_err().status, 400);
+        assert_eq!(Query::parse("ngrams=0").unwrap_err().status, 400);
+        assert_eq!(Query::parse("format=pdf").unwrap_err().status, 400);
+        assert_eq!(Query::parse("colour=blue").unwrap_err().status, 400);
+        assert_eq!(Query::parse("relative=true").unwrap(), Query::parse("").unwrap());
+        assert_eq!(Query::parse("relative").unwrap(), Query::parse("").unwrap());
+        assert_eq!(Query::parse("relative=maybe").unwrap_err().status, 400);
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a+b%20c%2Fd%zz%4"), "a b c/d%zz%4");
+        assert_eq!(percent_decode("%C3%A9"), "é");
+    }
+
+    #[test]
+    fn test_multipart() {
+        let body = b"preamble\r\n--XyZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.html\"\r\n\
+            Content-Type: text/html\r\n\r\n<p>cats</p>\r\n--XyZ\r\n\
+            Content-Disposition: form-data; name=\"text\"\r\n\r\ndogs\r\n--XyZ--\r\n";
+        let boundary = multipart_boundary("multipart/form-data; boundary=\"XyZ\"").unwrap();
+        let parts = parse_multipart(body, &boundary).unwrap();
+        assert_eq!(
+            parts,
+            [
+                (Some("a.html".to_string()), b"<p>cats</p>".to_vec()),
+                (None, b"dogs".to_vec())
+            ]
+        );
+        assert_eq!(multipart_boundary("text/plain"), None);
+        assert!(parse_multipart(b"--XyZ\r\nno end", "XyZ").is_err());
+    }
+
+    #[test]
+    fn test_post_text() {
+        let (status, body) = request(
+            "POST",
+            "/frequencies?num_words=2",
+            "text/plain",
+            b"The cats, the CATS and a dog",
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["total"], 3);
+        assert_eq!(word_counts(&body), [("cats", 2), ("dog", 1)]);
+
+        let (_, body) = request("POST", "/frequencies?stop_words=false&num_words=1", "", b"a a b c");
+        assert_eq!(
+            body,
+            json!({
+                "total": 4,
+                "frequencies": [
+                    { "word": "a", "count": 2, "percent": 50.0, "per_million": 500000.0, "relative": 100.0 }
+                ]
+            })
+        );
+
+        let (_, body) = request("POST", "/frequencies?ngrams=2", "", b"new york, new york");
+        assert_eq!(body["total"], 2);
+        assert_eq!(word_counts(&body), [("new york", 2)]);
+    }
+
+    #[test]
+    fn test_post_multipart() {
+        let body = b"--b\r\nContent-Disposition: form-data; name=\"f\"; filename=\"a.html\"\r\n\r\n<b>cats</b>\r\n\
+            --b\r\nContent-Disposition: form-data; name=\"g\"; filename=\"b.txt\"\r\n\r\ncats <b>\r\n--b--\r\n";
+        let (status, body) = request("POST", "/frequencies", "multipart/form-data; boundary=b", body);
+        assert_eq!(status, 200);
+        assert_eq!(body["total"], 3);
+        assert_eq!(word_counts(&body), [("cats", 2), ("b", 1)]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let (status, body) = request("GET", "/frequencies", "", b"");
+        assert_eq!(status, 405);
+        assert!(body["error"].is_string());
+        assert_eq!(request("POST", "/other", "", b"").0, 404);
+        assert_eq!(request("POST", "/frequencies?num_words=-1", "", b"").0, 400);
+        assert_eq!(request("POST", "/frequencies", "", b"\xff\xfe").0, 400);
+    }
+
+    /// Starts a server on a free port, sends a single request to it and gives the status and JSON body of the
+    /// response.
+    fn request(method: &str, path: &str, content_type: &str, body: &[u8]) -> (u16, Value) {
+        let server = Server::bind("127.0.0.1:0", Some("stop-words.txt")).unwrap();
+        let address = server.local_addr();
+        let handle = thread::spawn(move || server.handle_next().unwrap());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let headers = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            method,
+            path,
+            content_type,
+            body.len()
+        );
+        stream.write_all(headers.as_bytes()).unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        handle.join().unwrap();
+
+        let status = response[9..12].parse().unwrap();
+        let (_, response_body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(response_body).unwrap())
+    }
+
+    fn word_counts(body: &Value) -> Vec<(&str, u64)> {
+        let frequencies = body["frequencies"].as_array().unwrap();
+        frequencies.iter().map(|f| (f["word"].as_str().unwrap(), f["count"].as_u64().unwrap())).collect()
+    }
+}
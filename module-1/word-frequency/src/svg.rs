@@ -0,0 +1,264 @@
+//!
+//! Contains code to render word counts as SVG images: a word cloud, where the font size of each word grows with its
+//! count, and a horizontal bar chart.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::frequency::Count;
+//! use word_frequency::svg;
+//!
+//! let counts = vec![Count::new("cat", 3), Count::new("dog", 1)];
+//! let cloud = svg::word_cloud(&counts);
+//! assert!(cloud.starts_with("<svg"));
+//! assert!(cloud.contains(">cat</text>"));
+//! let chart = svg::bar_chart(&counts);
+//! assert_eq!(chart.matches("<rect").count(), 2);
+//! ```
+
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use unicode_width::UnicodeWidthStr;
+
+use crate::frequency::Count;
+
+/// The kinds of charts that can be rendered.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, ValueEnum)]
+pub enum ChartKind {
+    /// A word cloud, with the most common words in the largest font
+    Cloud,
+    /// A horizontal bar chart
+    Bar,
+}
+
+/// The font size of the least common word in a word cloud.
+const MIN_FONT_SIZE: f64 = 12.0;
+/// The font size of the most common word in a word cloud.
+const MAX_FONT_SIZE: f64 = 72.0;
+/// The width of a column relative to the font size, which is exact for the monospace fonts we use. Wide characters,
+/// e.g., Chinese or Japanese ones, take two columns.
+const CHAR_WIDTH: f64 = 0.6;
+/// The space around the words in a word cloud and around a bar chart.
+const MARGIN: f64 = 10.0;
+/// The height of a bar in a bar chart.
+const BAR_HEIGHT: f64 = 20.0;
+/// The space between two bars in a bar chart.
+const BAR_GAP: f64 = 6.0;
+/// The length of the bar of the most common word in a bar chart.
+const MAX_BAR_LENGTH: f64 = 500.0;
+/// The font size of the words and counts in a bar chart.
+const FONT_SIZE: f64 = 14.0;
+const COLORS: [&str; 6] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b"];
+
+/// Renders the given counts as a chart of the given kind.
+pub fn render(kind: ChartKind, counts: &[Count]) -> String {
+    match kind {
+        ChartKind::Cloud => word_cloud(counts),
+        ChartKind::Bar => bar_chart(counts),
+    }
+}
+
+/// Renders the given counts as a word cloud, where the font size of a word grows with the square root of its count,
+/// so that the area of a word is roughly proportional to its count. The words are placed along a spiral from the
+/// center, in the given order, at the first position where they do not overlap a word already placed. The image is
+/// made just large enough to fit all words.
+pub fn word_cloud(counts: &[Count]) -> String {
+    let placements = place_words(counts);
+    if placements.is_empty() {
+        return svg_document(0.0, 0.0, 0.0, 0.0, "");
+    }
+    let min_x = placements.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+    let min_y = placements.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+    let max_x = placements.iter().map(|p| p.x + p.width).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = placements.iter().map(|p| p.y + p.height).fold(f64::NEG_INFINITY, f64::max);
+
+    let mut elements = String::new();
+    for (i, placement) in placements.iter().enumerate() {
+        writeln!(
+            elements,
+            r#"  <text x="{:.1}" y="{:.1}" font-size="{:.1}" fill="{}">{}</text>"#,
+            placement.x + placement.width / 2.0,
+            placement.y + placement.height / 2.0,
+            placement.font_size,
+            COLORS[i % COLORS.len()],
+            escape(placement.word)
+        )
+        .unwrap();
+    }
+    svg_document(
+        min_x - MARGIN,
+        min_y - MARGIN,
+        max_x - min_x + 2.0 * MARGIN,
+        max_y - min_y + 2.0 * MARGIN,
+        &elements,
+    )
+}
+
+/// Renders the given counts as a horizontal bar chart, with one bar per word in the given order, the word to the
+/// left of the bar and the count to the right of it.
+pub fn bar_chart(counts: &[Count]) -> String {
+    let max_count = counts.iter().map(|c| c.count).max().unwrap_or(0).max(1) as f64;
+    let label_width = counts.iter().map(|c| text_width(c.word, FONT_SIZE)).fold(0.0, f64::max);
+    let bar_x = MARGIN + label_width + MARGIN;
+    let mut elements = String::new();
+    for (i, count) in counts.iter().enumerate() {
+        let y = MARGIN + i as f64 * (BAR_HEIGHT + BAR_GAP);
+        let length = count.count as f64 / max_count * MAX_BAR_LENGTH;
+        writeln!(
+            elements,
+            r#"  <text x="{:.1}" y="{:.1}" font-size="{}" text-anchor="end">{}</text>"#,
+            bar_x - MARGIN / 2.0,
+            y + BAR_HEIGHT / 2.0,
+            FONT_SIZE,
+            escape(count.word)
+        )
+        .unwrap();
+        writeln!(
+            elements,
+            r#"  <rect x="{:.1}" y="{:.1}" width="{:.1}" height="{}" fill="{}"/>"#,
+            bar_x, y, length, BAR_HEIGHT, COLORS[0]
+        )
+        .unwrap();
+        writeln!(
+            elements,
+            r#"  <text x="{:.1}" y="{:.1}" font-size="{}" text-anchor="start">{}</text>"#,
+            bar_x + length + MARGIN / 2.0,
+            y + BAR_HEIGHT / 2.0,
+            FONT_SIZE,
+            count.count
+        )
+        .unwrap();
+    }
+    let max_count_width = text_width(&max_count.to_string(), FONT_SIZE);
+    let width = bar_x + MAX_BAR_LENGTH + MARGIN / 2.0 + max_count_width + MARGIN;
+    let height = 2.0 * MARGIN + counts.len() as f64 * (BAR_HEIGHT + BAR_GAP) - BAR_GAP;
+    svg_document(0.0, 0.0, width, height.max(0.0), &elements)
+}
+
+/// The bounding box of a word in a word cloud.
+#[derive(Debug)]
+struct Placement<'a> {
+    word: &'a str,
+    font_size: f64,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Placement<'_> {
+    fn overlaps(&self, other: &Placement) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+fn place_words<'a>(counts: &[Count<'a>]) -> Vec<Placement<'a>> {
+    let max_count = counts.iter().map(|c| c.count).max().unwrap_or(0).max(1) as f64;
+    let mut placements: Vec<Placement> = Vec::with_capacity(counts.len());
+    for count in counts {
+        let font_size = MIN_FONT_SIZE + (MAX_FONT_SIZE - MIN_FONT_SIZE) * (count.count as f64 / max_count).sqrt();
+        let width = text_width(count.word, font_size);
+        let height = font_size;
+        // Follow an Archimedean spiral, with the points about two pixels apart, until there is room for the word. The
+        // spiral is flattened vertically, since words are wider than they are high.
+        let mut angle: f64 = 0.0;
+        loop {
+            let radius = 2.0 * angle;
+            let placement = Placement {
+                word: count.word,
+                font_size,
+                x: radius * angle.cos() - width / 2.0,
+                y: radius * angle.sin() * 0.6 - height / 2.0,
+                width,
+                height,
+            };
+            if !placements.iter().any(|other| placement.overlaps(other)) {
+                placements.push(placement);
+                break;
+            }
+            angle += 1.0 / (1.0 + angle);
+        }
+    }
+    placements
+}
+
+fn text_width(text: &str, font_size: f64) -> f64 {
+    text.width() as f64 * font_size * CHAR_WIDTH
+}
+
+fn svg_document(x: f64, y: f64, width: f64, height: f64, elements: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{x:.1} {y:.1} {width:.1} {height:.1}\" \
+         width=\"{width:.0}\" height=\"{height:.0}\" font-family=\"monospace\" dominant-baseline=\"central\" \
+         text-anchor=\"middle\">\n{elements}</svg>\n"
+    )
+}
+
+/// Escapes the characters that are special in XML text and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_place_words_without_overlap() {
+        // Every third word is wide, to check that wide characters are measured as such
+        let word =
+            |i: usize| if i.is_multiple_of(3) { "東京".repeat(i % 4 + 1) } else { format!("word{}", "x".repeat(i % 7)) };
+        let words: Vec<String> = (0..60).map(word).collect();
+        let counts: Vec<Count> = words.iter().enumerate().map(|(i, w)| Count::new(w, 100 - i)).collect();
+        let placements = place_words(&counts);
+        assert_eq!(placements.len(), counts.len());
+        for (i, a) in placements.iter().enumerate() {
+            for b in &placements[i + 1..] {
+                assert!(!a.overlaps(b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+        assert_eq!(placements[0].font_size, MAX_FONT_SIZE);
+        assert!(placements[59].font_size < placements[0].font_size);
+    }
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(text_width("cat", 10.0), 18.0);
+        assert_eq!(text_width("café", 10.0), 24.0);
+        assert_eq!(text_width("東京", 10.0), 24.0);
+    }
+
+    #[test]
+    fn test_word_cloud() {
+        let counts = vec![Count::new("rock & roll", 4), Count::new("<b>", 1)];
+        let svg = word_cloud(&counts);
+        assert!(svg.contains(r##"font-size="72.0" fill="#1f77b4">rock &amp; roll</text>"##));
+        assert!(svg.contains(r##"font-size="42.0" fill="#ff7f0e">&lt;b&gt;</text>"##));
+        assert_eq!(word_cloud(&[]).matches("<text").count(), 0);
+    }
+
+    #[test]
+    fn test_bar_chart() {
+        let counts = vec![Count::new("cat", 10), Count::new("dog", 5)];
+        let svg = bar_chart(&counts);
+        assert!(svg.contains(r#"<rect x="45.2" y="10.0" width="500.0" height="20""#));
+        assert!(svg.contains(r#"<rect x="45.2" y="36.0" width="250.0" height="20""#));
+        assert!(svg.contains(">dog</text>"));
+        assert!(svg.contains(">5</text>"));
+    }
+}
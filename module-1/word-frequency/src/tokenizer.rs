@@ -29,10 +29,23 @@ pub struct Tokenizer {
 pub enum Token {
     Word(String),
     Number(i64),
+    /// A sequence of numeric characters that can't be represented as an `i64`, e.g., because it is too large or
+    /// contains characters like '½' or '²'
+    Numeral(String),
     Whitespace,
     Punctuation(char),
 }
 
+/// The kind of token read by [`Tokenizer::read_into()`], which puts the text of a word in a buffer instead of a new
+/// `String`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(crate) enum Lexeme {
+    Word,
+    Whitespace,
+    /// A number, a numeral or punctuation
+    Other,
+}
+
 impl Tokenizer {
     /// Creates a new `Tokenizer` that breaks the given string into tokens, starting at the first character.
     pub fn new(input: &str) -> Self {
@@ -52,6 +65,37 @@ impl Tokenizer {
         }
     }
 
+    /// Reads the next token like [`Self::next_token()`], but only tells its kind, and replaces the contents of the
+    /// given buffer with the text if it is a word. This avoids allocating a `String` for every word when counting.
+    pub(crate) fn read_into(&mut self, word: &mut String) -> Option<Lexeme> {
+        match self.peek_char()? {
+            c if c.is_whitespace() => {
+                self.position += 1;
+                Some(Lexeme::Whitespace)
+            }
+            c if c.is_alphabetic() => {
+                let start = self.position;
+                self.skip_word();
+                word.clear();
+                word.extend(&self.input[start..self.position]);
+                Some(Lexeme::Word)
+            }
+            c if c.is_numeric() => {
+                self.skip_number();
+                Some(Lexeme::Other)
+            }
+            _ => {
+                self.position += 1;
+                Some(Lexeme::Other)
+            }
+        }
+    }
+
+    /// Gives the position of the next token, as the number of characters before it in the input.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
     fn read_whitespace(&mut self) -> Option<Token> {
         self.position += 1;
         Some(Token::Whitespace)
@@ -59,6 +103,11 @@ impl Tokenizer {
 
     fn read_word(&mut self) -> Option<Token> {
         let start = self.position;
+        self.skip_word();
+        Some(Token::Word(self.input[start..self.position].iter().collect()))
+    }
+
+    fn skip_word(&mut self) {
         while let Some(ch) = self.peek_char() {
             if ch.is_alphabetic() {
                 self.position += 1;
@@ -68,7 +117,6 @@ impl Tokenizer {
                 break;
             }
         }
-        Some(Token::Word(self.input[start..self.position].iter().collect()))
     }
 
     #[cfg(feature = "count-apostrophed-words-as-one")]
@@ -93,6 +141,15 @@ impl Tokenizer {
 
     fn read_number(&mut self) -> Option<Token> {
         let start = self.position;
+        self.skip_number();
+        let number_str: String = (self.input[start..self.position]).iter().collect();
+        match number_str.parse::<i64>() {
+            Ok(number) => Some(Token::Number(number)),
+            Err(_) => Some(Token::Numeral(number_str)),
+        }
+    }
+
+    fn skip_number(&mut self) {
         while let Some(ch) = self.peek_char() {
             if ch.is_numeric() {
                 self.position += 1;
@@ -100,9 +157,6 @@ impl Tokenizer {
                 break;
             }
         }
-        let number_str: String = (self.input[start..self.position]).iter().collect();
-        let number = number_str.parse::<i64>().unwrap();
-        Some(Token::Number(number))
     }
 
     fn read_punctuation(&mut self, c: char) -> Option<Token> {
@@ -126,9 +180,21 @@ impl Tokenizer {
     }
 }
 
+impl Iterator for Tokenizer {
+    type Item = Token;
+
+    /// Gives the next `Token`, see [`Tokenizer::next_token()`].
+    fn next(&mut self) -> Option<Token> {
+        self.next_token()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
+    use crate::frequency::FrequencyCounter;
 
     #[test]
     fn test_tokenizer_creation() {
@@ -236,6 +302,88 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tokenize_numbers() {
+        let mut tokenizer = Tokenizer::new("42 99999999999999999999 ½ ² ٣");
+        assert_tokens(
+            &mut tokenizer,
+            &[
+                Token::Number(42),
+                Token::Whitespace,
+                Token::Numeral("99999999999999999999".to_string()),
+                Token::Whitespace,
+                Token::Numeral("½".to_string()),
+                Token::Whitespace,
+                Token::Numeral("²".to_string()),
+                Token::Whitespace,
+                Token::Numeral("٣".to_string()),
+            ],
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_tokens_reproduce_input(input in "\\PC*|.*") {
+            let chars: Vec<char> = input.chars().collect();
+            let mut tokenizer = Tokenizer::new(&input);
+            let mut reproduced = String::new();
+            let mut start = tokenizer.position();
+            while let Some(token) = tokenizer.next_token() {
+                let end = tokenizer.position();
+                prop_assert!(end > start, "no progress at {}", start);
+                let text: String = chars[start..end].iter().collect();
+                assert_token_text(&token, &text)?;
+                reproduced.push_str(&text);
+                start = end;
+            }
+            prop_assert_eq!(start, chars.len());
+            prop_assert_eq!(reproduced, input);
+        }
+
+        #[test]
+        fn prop_never_panics(input in any::<String>()) {
+            let mut tokenizer = Tokenizer::new(&input);
+            while tokenizer.next_token().is_some() {}
+            let mut frequency_counter = FrequencyCounter::new(&input);
+            frequency_counter.count_ngrams(2);
+        }
+
+        #[test]
+        fn prop_counts_invariant_under_whitespace(
+            parts in proptest::collection::vec(("[^\\s]+", "\\s{1,3}"), 0..20)
+        ) {
+            let original: Vec<String> = parts.iter().map(|(text, _)| text.clone()).collect();
+            let respaced: String = parts.iter().map(|(text, whitespace)| format!("{}{}", text, whitespace)).collect();
+            prop_assert_eq!(
+                FrequencyCounter::new(&original.join(" ")).count_frequencies(),
+                FrequencyCounter::new(&respaced).count_frequencies()
+            );
+        }
+
+        #[test]
+        fn prop_read_into_counts_like_tokens(input in "\\PC*|[a-c 1.]*", n in 1usize..4) {
+            // Counting reads the words into a buffer, which must give the same counts as the tokens
+            let tokens: Vec<Token> = Tokenizer::new(&input).collect();
+            prop_assert_eq!(
+                FrequencyCounter::new(&input).count_ngrams(n),
+                FrequencyCounter::new("").count_tokens(tokens, n)
+            );
+        }
+    }
+
+    /// Checks that a token matches the text it was read from.
+    fn assert_token_text(token: &Token, text: &str) -> Result<(), TestCaseError> {
+        match token {
+            Token::Word(word) => prop_assert_eq!(word, text),
+            // The text may have leading zeros, so compare the values
+            Token::Number(number) => prop_assert_eq!(Ok(*number), text.parse::<i64>()),
+            Token::Numeral(numeral) => prop_assert_eq!(numeral, text),
+            Token::Whitespace => prop_assert!(text.chars().count() == 1 && text.chars().all(char::is_whitespace)),
+            Token::Punctuation(c) => prop_assert_eq!(c.to_string(), text),
+        }
+        Ok(())
+    }
+
     fn assert_tokens(tokenizer: &mut Tokenizer, tokens: &[Token]) {
         for token in tokens {
             assert_eq!(tokenizer.next_token(), Some(token.clone()));
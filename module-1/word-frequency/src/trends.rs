@@ -0,0 +1,350 @@
+//!
+//! Contains code to count words in time windows over timestamped input, such as log files or chat exports, and to
+//! find the words that are trending, i.e., whose number of occurrences rose most from one window to the next.
+//!
+//! Each line of the input is expected to start with a timestamp in a configurable [`chrono` format], e.g.,
+//! `%Y-%m-%d %H:%M:%S` or `%s` for seconds since the Unix epoch. Lines without a timestamp are counted in the same
+//! window as the line before, so multi-line messages are handled, and lines before the first timestamp are skipped.
+//!
+//! Words are first counted in buckets of one window step each, and the buckets are then added up to give windows
+//! that are tumbling, i.e., following each other without overlap, when the window size equals the step, or sliding,
+//! i.e., overlapping, when the window size is a multiple of the step. A window is compared with the window that ends
+//! where it starts, so trending words are found between windows that do not overlap.
+//!
+//! [`chrono` format]: https://docs.rs/chrono/latest/chrono/format/strftime/index.html
+//!
+//! # Examples
+//!
+//! ```
+//! use chrono::TimeDelta;
+//! use word_frequency::trends::{self, WindowedCounter};
+//!
+//! let log = "2024-05-01 10:05:00 cats sleep\n\
+//!            2024-05-01 10:40:00 dogs bark\n\
+//!            2024-05-01 11:10:00 cats eat\n\
+//!            2024-05-01 11:20:00 cats play\n";
+//! let mut counter = WindowedCounter::new("%Y-%m-%d %H:%M:%S", TimeDelta::hours(1), TimeDelta::hours(1)).unwrap();
+//! counter.read_lines(Box::new(log.as_bytes())).unwrap();
+//! let windows = counter.windows();
+//! assert_eq!(windows.len(), 2);
+//! assert_eq!(windows[1].start.to_string(), "2024-05-01 11:00:00");
+//! assert_eq!(windows[1].word_frequencies["cats"], 2);
+//!
+//! let previous = counter.previous_window(&windows[1]);
+//! let trending = trends::trending(&previous.word_frequencies, &windows[1].word_frequencies, 1);
+//! assert_eq!(trending[0].word, "cats");
+//! assert_eq!(trending[0].increase(), 1);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{BufRead, BufReader, Read};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+
+use crate::compression;
+use crate::error::{Error, Result};
+use crate::frequency::FrequencyCounter;
+
+/// The largest number of steps in a window, e.g., a day in steps of a minute. Every bucket is part of this many
+/// sliding windows, and each window adds up this many buckets, so larger numbers make counting very slow.
+pub const MAX_STEPS_PER_WINDOW: i64 = 1440;
+
+/// Counts words per time window, based on a timestamp at the start of each line.
+#[derive(Debug)]
+pub struct WindowedCounter {
+    /// Counts the words of each line, and knows the stop words
+    frequency_counter: FrequencyCounter,
+    /// The format of the timestamps at the start of the lines
+    timestamp_format: String,
+    /// The length of a window, in seconds, always a multiple of the step
+    size: i64,
+    /// The time between the starts of two consecutive windows, in seconds
+    step: i64,
+    /// The word frequencies of each bucket, keyed by the number of steps from the Unix epoch to the start of the bucket
+    buckets: BTreeMap<i64, HashMap<String, usize>>,
+    /// The bucket of the latest timestamp seen, used for lines without a timestamp
+    current_bucket: Option<i64>,
+}
+
+/// The word frequencies in the time window from `start`, inclusive, to `end`, exclusive.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Window {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub word_frequencies: HashMap<String, usize>,
+}
+
+/// A word whose number of occurrences rose from one window to the next.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Trend<'a> {
+    pub word: &'a str,
+    pub previous_count: usize,
+    pub count: usize,
+}
+
+impl<'a> Trend<'a> {
+    /// Gives how many more times the word occurred in the later window than in the earlier one.
+    pub fn increase(&self) -> usize {
+        self.count - self.previous_count
+    }
+}
+
+impl WindowedCounter {
+    /// Creates a new `WindowedCounter` for lines starting with timestamps in the given format, counting words in
+    /// windows of the given size, starting every `step`. Use the same size and step for tumbling windows.
+    ///
+    /// Gives an [`Error::Config`] if the size or step is not a positive number of whole seconds, or if the size is
+    /// not a multiple of the step, or more than [`MAX_STEPS_PER_WINDOW`] steps.
+    pub fn new(timestamp_format: &str, size: TimeDelta, step: TimeDelta) -> Result<Self> {
+        let (size, step) = (whole_seconds(size)?, whole_seconds(step)?);
+        if size % step != 0 {
+            return Err(Error::Config(format!(
+                "the window size ({}s) must be a multiple of the window step ({}s)",
+                size, step
+            )));
+        }
+        if size / step > MAX_STEPS_PER_WINDOW {
+            return Err(Error::Config(format!(
+                "the window size ({}s) may be at most {} times the window step ({}s)",
+                size, MAX_STEPS_PER_WINDOW, step
+            )));
+        }
+        Ok(Self {
+            frequency_counter: FrequencyCounter::new(""),
+            timestamp_format: timestamp_format.to_string(),
+            size,
+            step,
+            buckets: BTreeMap::new(),
+            current_bucket: None,
+        })
+    }
+
+    /// Reads a file with stop words, see [`FrequencyCounter::read_stop_words()`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_stop_words(&mut self, file_name: &str) -> Result<()> {
+        self.frequency_counter.read_stop_words(file_name)
+    }
+
+    /// Reads a file with multi-word expressions, see [`FrequencyCounter::read_phrases()`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_phrases(&mut self, file_name: &str) -> Result<()> {
+        self.frequency_counter.read_phrases(file_name)
+    }
+
+    /// Reads and counts all lines from the given `Read` instance, decompressing it if needed like
+    /// [`FrequencyCounter::from_reader()`].
+    pub fn read_lines(&mut self, reader: Box<dyn Read>) -> Result<()> {
+        for line in BufReader::new(compression::decompress(reader)?).lines() {
+            self.add_line(&line.map_err(Error::reading)?);
+        }
+        Ok(())
+    }
+
+    /// Counts the words of a single line, in the window of the timestamp at the start of the line, or of the latest
+    /// timestamp seen if the line has no timestamp. The timestamp itself is not counted.
+    pub fn add_line(&mut self, line: &str) {
+        let text = match parse_timestamp(line, &self.timestamp_format) {
+            Some((timestamp, rest)) => {
+                self.current_bucket = Some(timestamp.and_utc().timestamp().div_euclid(self.step));
+                rest
+            }
+            None => line,
+        };
+        if let Some(bucket) = self.current_bucket {
+            self.frequency_counter.feed(&text.to_lowercase());
+            self.frequency_counter.add_frequencies(self.buckets.entry(bucket).or_default());
+        }
+    }
+
+    /// Gives all windows that contain at least one word, in order of time. Sliding windows overlapping the start or
+    /// end of the input are included, so every bucket is part of `size / step` windows.
+    pub fn windows(&self) -> Vec<Window> {
+        let steps_per_window = self.size / self.step;
+        let starts: BTreeSet<i64> = self
+            .buckets
+            .iter()
+            .filter(|(_, word_frequencies)| !word_frequencies.is_empty())
+            .flat_map(|(&bucket, _)| (bucket - steps_per_window + 1)..=bucket)
+            .collect();
+        starts.into_iter().map(|start| self.window(start)).collect()
+    }
+
+    /// Gives the window that ends where the given window starts, which may be empty. For tumbling windows, this is
+    /// the window before, and for sliding windows, it is the last window that does not overlap the given one.
+    pub fn previous_window(&self, window: &Window) -> Window {
+        self.window(window.start.and_utc().timestamp().div_euclid(self.step) - self.size / self.step)
+    }
+
+    fn window(&self, start_bucket: i64) -> Window {
+        let end_bucket = start_bucket + self.size / self.step;
+        let mut word_frequencies = HashMap::new();
+        for bucket_frequencies in self.buckets.range(start_bucket..end_bucket).map(|(_, f)| f) {
+            for (word, count) in bucket_frequencies {
+                *word_frequencies.entry(word.clone()).or_insert(0) += count;
+            }
+        }
+        Window {
+            start: bucket_time(start_bucket * self.step),
+            end: bucket_time(end_bucket * self.step),
+            word_frequencies,
+        }
+    }
+}
+
+/// Gives up to `num_words` words whose number of occurrences rose most from the `previous` to the `current` word
+/// frequencies, sorted by the increase in descending order, and then by word.
+pub fn trending<'a>(
+    previous: &HashMap<String, usize>,
+    current: &'a HashMap<String, usize>,
+    num_words: usize,
+) -> Vec<Trend<'a>> {
+    let mut trends: Vec<Trend> = current
+        .iter()
+        .map(|(word, &count)| Trend {
+            word,
+            previous_count: previous.get(word).copied().unwrap_or(0),
+            count,
+        })
+        .filter(|trend| trend.count > trend.previous_count)
+        .collect();
+    trends.sort_by(|a, b| b.increase().cmp(&a.increase()).then(a.word.cmp(b.word)));
+    trends.truncate(num_words);
+    trends
+}
+
+/// Parses a duration like `90s`, `15m`, `1h`, `2d` or `1w`.
+pub fn parse_duration(duration: &str) -> Result<TimeDelta> {
+    let duration = duration.trim();
+    let unit_start = duration.find(|c: char| !c.is_ascii_digit()).unwrap_or(duration.len());
+    let invalid =
+        || Error::Config(format!("invalid duration '{}', expected, e.g., '30s', '15m', '1h' or '1d'", duration));
+    let amount: i64 = duration[..unit_start].parse().map_err(|_| invalid())?;
+    match &duration[unit_start..] {
+        "s" => TimeDelta::try_seconds(amount),
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => None,
+    }
+    .ok_or_else(invalid)
+}
+
+fn whole_seconds(duration: TimeDelta) -> Result<i64> {
+    if duration.subsec_nanos() != 0 || duration.num_seconds() <= 0 {
+        return Err(Error::Config(format!("the window size and step must be whole seconds, not {}", duration)));
+    }
+    Ok(duration.num_seconds())
+}
+
+fn bucket_time(seconds: i64) -> NaiveDateTime {
+    DateTime::from_timestamp(seconds, 0).unwrap_or_default().naive_utc()
+}
+
+/// Parses a timestamp in the given format at the start of the line, giving the timestamp and the rest of the line.
+/// Timestamps with a time zone are converted to UTC, and dates without a time are taken to be at midnight.
+fn parse_timestamp<'a>(line: &'a str, format: &str) -> Option<(NaiveDateTime, &'a str)> {
+    let line = line.trim_start();
+    if let Ok((timestamp, rest)) = DateTime::parse_and_remainder(line, format) {
+        return Some((timestamp.naive_utc(), rest));
+    }
+    if let Ok((timestamp, rest)) = NaiveDateTime::parse_and_remainder(line, format) {
+        return Some((timestamp, rest));
+    }
+    NaiveDate::parse_and_remainder(line, format).ok().map(|(date, rest)| (date.and_time(NaiveTime::MIN), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let (timestamp, rest) = parse_timestamp("2024-05-01T10:05:00 hello", "%Y-%m-%dT%H:%M:%S").unwrap();
+        assert_eq!(timestamp.to_string(), "2024-05-01 10:05:00");
+        assert_eq!(rest, " hello");
+        let (timestamp, _) = parse_timestamp("[01/May/2024:10:05:00 +0200] GET", "[%d/%b/%Y:%H:%M:%S %z]").unwrap();
+        assert_eq!(timestamp.to_string(), "2024-05-01 08:05:00");
+        let (timestamp, _) = parse_timestamp("1714557900 hello", "%s").unwrap();
+        assert_eq!(timestamp.to_string(), "2024-05-01 10:05:00");
+        let (timestamp, _) = parse_timestamp("2024-05-01 hello", "%Y-%m-%d").unwrap();
+        assert_eq!(timestamp.to_string(), "2024-05-01 00:00:00");
+        assert_eq!(parse_timestamp("hello 2024-05-01", "%Y-%m-%d"), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s").unwrap(), TimeDelta::seconds(90));
+        assert_eq!(parse_duration("15m").unwrap(), TimeDelta::minutes(15));
+        assert_eq!(parse_duration("1h").unwrap(), TimeDelta::hours(1));
+        assert_eq!(parse_duration("2d").unwrap(), TimeDelta::days(2));
+        assert_eq!(parse_duration("1w").unwrap(), TimeDelta::weeks(1));
+        assert!(parse_duration("1").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("1y").is_err());
+        assert!(parse_duration("-1h").is_err());
+    }
+
+    #[test]
+    fn test_invalid_window() {
+        assert!(WindowedCounter::new("%s", TimeDelta::minutes(90), TimeDelta::hours(1)).is_err());
+        assert!(WindowedCounter::new("%s", TimeDelta::zero(), TimeDelta::zero()).is_err());
+        assert!(WindowedCounter::new("%s", TimeDelta::milliseconds(1500), TimeDelta::milliseconds(500)).is_err());
+        assert!(WindowedCounter::new("%s", TimeDelta::days(1), TimeDelta::seconds(1)).is_err());
+        assert!(WindowedCounter::new("%s", TimeDelta::days(1), TimeDelta::minutes(1)).is_ok());
+    }
+
+    #[test]
+    fn test_tumbling_windows() {
+        let mut counter = WindowedCounter::new("%s", TimeDelta::seconds(10), TimeDelta::seconds(10)).unwrap();
+        counter.add_line("no timestamp yet, skipped");
+        counter.add_line("5 cats");
+        counter.add_line("continued cats");
+        counter.add_line("12 dogs");
+        counter.add_line("35 cats");
+        let windows = counter.windows();
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].start, bucket_time(0));
+        assert_eq!(windows[0].end, bucket_time(10));
+        assert_eq!(windows[0].word_frequencies, frequencies(&[("cats", 2), ("continued", 1)]));
+        assert_eq!(windows[1].word_frequencies, frequencies(&[("dogs", 1)]));
+        assert_eq!(windows[2].start, bucket_time(30));
+        assert_eq!(windows[2].word_frequencies, frequencies(&[("cats", 1)]));
+        assert_eq!(counter.previous_window(&windows[2]).word_frequencies, HashMap::new());
+    }
+
+    #[test]
+    fn test_sliding_windows() {
+        let mut counter = WindowedCounter::new("%s", TimeDelta::seconds(20), TimeDelta::seconds(10)).unwrap();
+        counter.add_line("5 cats");
+        counter.add_line("15 dogs");
+        let windows = counter.windows();
+        let starts: Vec<i64> = windows.iter().map(|w| w.start.and_utc().timestamp()).collect();
+        assert_eq!(starts, [-10, 0, 10]);
+        assert_eq!(windows[0].word_frequencies, frequencies(&[("cats", 1)]));
+        assert_eq!(windows[1].word_frequencies, frequencies(&[("cats", 1), ("dogs", 1)]));
+        assert_eq!(windows[2].word_frequencies, frequencies(&[("dogs", 1)]));
+        let previous = counter.previous_window(&windows[2]);
+        assert_eq!(previous.start.and_utc().timestamp(), -10);
+        assert_eq!(previous.end, windows[2].start);
+        assert_eq!(previous.word_frequencies, frequencies(&[("cats", 1)]));
+    }
+
+    #[test]
+    fn test_trending() {
+        let previous = frequencies(&[("cats", 3), ("dogs", 1), ("birds", 5)]);
+        let current = frequencies(&[("cats", 3), ("dogs", 4), ("fish", 3), ("birds", 1), ("ants", 1)]);
+        let trends = trending(&previous, &current, 2);
+        assert_eq!(
+            trends,
+            [
+                Trend { word: "dogs", previous_count: 1, count: 4 },
+                Trend { word: "fish", previous_count: 0, count: 3 },
+            ]
+        );
+    }
+
+    fn frequencies(counts: &[(&str, usize)]) -> HashMap<String, usize> {
+        counts.iter().map(|(word, count)| (word.to_string(), *count)).collect()
+    }
+}
@@ -0,0 +1,459 @@
+//!
+//! Contains an interactive terminal user interface for exploring the word frequencies of a text.
+//!
+//! The user interface shows the words sorted by frequency in a scrollable table, and lets you:
+//!
+//! - search for words, showing only the words containing the search text (`/`)
+//! - switch between absolute counts, counts relative to the most common word, percentages and counts per million
+//!   words (`r`)
+//! - toggle between ignoring and counting the stop words (`s`)
+//! - see every occurrence of the selected word in its context (`Enter`)
+//!
+//! The state of the user interface is kept in an [`App`], which can be tested without a terminal.
+
+use std::collections::{HashMap, HashSet};
+
+use clap::ValueEnum;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListState, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::error::Result;
+use crate::frequency::{FrequencyCounter, Scale};
+use crate::tokenizer::{Token, Tokenizer};
+
+/// The number of characters shown on each side of a word when showing its occurrences.
+const CONTEXT_LENGTH: usize = 40;
+
+/// The state of the terminal user interface.
+#[derive(Debug)]
+pub struct App {
+    /// The name of the input, shown in the title
+    title: String,
+    /// The text being explored, used to find the occurrences of a word
+    text: String,
+    /// The number of occurrences of each word in the text, including the stop words
+    word_frequencies: HashMap<String, usize>,
+    stop_words: HashSet<String>,
+    ignore_stop_words: bool,
+    scale: Scale,
+    /// Only words containing this text are shown
+    filter: String,
+    mode: Mode,
+    /// The rows of the table, sorted and filtered according to the current settings
+    rows: Vec<TableRow>,
+    table_state: TableState,
+    /// The number of rows that fit on the screen, used for paging
+    page_size: usize,
+}
+
+/// A row in the table of words.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TableRow {
+    /// The position of the word when sorted by frequency, starting at 1, ignoring the search filter
+    pub rank: usize,
+    pub word: String,
+    /// The number of occurrences
+    pub count: usize,
+    /// The count formatted on the current scale
+    pub value: String,
+}
+
+/// An occurrence of a word, with the text before and after it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Occurrence {
+    pub before: String,
+    pub word: String,
+    pub after: String,
+}
+
+#[derive(Debug)]
+enum Mode {
+    /// Browsing the table of words
+    Table,
+    /// Typing the search filter
+    Search,
+    /// Browsing the occurrences of a word
+    Occurrences { word: String, occurrences: Vec<Occurrence>, list_state: ListState },
+}
+
+impl App {
+    /// Creates a new `App` for exploring the given text, which should be in lower case like the text counted by
+    /// [`FrequencyCounter::from_reader()`]. The stop words are ignored from the start, and the counts are shown on the
+    /// given scale.
+    pub fn new(title: &str, text: String, stop_words: HashSet<String>, scale: Scale) -> Self {
+        let word_frequencies = FrequencyCounter::new(&text).count_frequencies();
+        let mut app = Self {
+            title: title.to_string(),
+            text,
+            word_frequencies,
+            stop_words,
+            ignore_stop_words: true,
+            scale,
+            filter: String::new(),
+            mode: Mode::Table,
+            rows: Vec::new(),
+            table_state: TableState::default(),
+            page_size: 10,
+        };
+        app.update_rows();
+        app
+    }
+
+    /// Gives the rows of the table, sorted and filtered according to the current settings.
+    pub fn rows(&self) -> &[TableRow] {
+        &self.rows
+    }
+
+    /// Gives the index of the selected row, if any.
+    pub fn selected(&self) -> Option<usize> {
+        self.table_state.selected()
+    }
+
+    /// Gives every occurrence of the given word in the text, with up to [`CONTEXT_LENGTH`] characters before and
+    /// after it. Line breaks in the context are shown as spaces.
+    pub fn occurrences(&self, word: &str) -> Vec<Occurrence> {
+        let chars: Vec<char> = self.text.chars().collect();
+        let context = |start: usize, end: usize| -> String {
+            chars[start..end].iter().map(|&c| if c.is_whitespace() { ' ' } else { c }).collect()
+        };
+        let mut occurrences = Vec::new();
+        let mut tokenizer = Tokenizer::new(&self.text);
+        let mut start = tokenizer.position();
+        while let Some(token) = tokenizer.next_token() {
+            let end = tokenizer.position();
+            if matches!(&token, Token::Word(w) if w == word) {
+                occurrences.push(Occurrence {
+                    before: context(start.saturating_sub(CONTEXT_LENGTH), start),
+                    word: word.to_string(),
+                    after: context(end, (end + CONTEXT_LENGTH).min(chars.len())),
+                });
+            }
+            start = end;
+        }
+        occurrences
+    }
+
+    /// Handles a key press, returning `false` if the user wants to quit.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match &mut self.mode {
+            Mode::Search => match key.code {
+                KeyCode::Enter => self.mode = Mode::Table,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Table;
+                    self.update_rows();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.update_rows();
+                }
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.update_rows();
+                }
+                _ => {}
+            },
+            Mode::Occurrences { occurrences, list_state, .. } => match key.code {
+                KeyCode::Esc | KeyCode::Backspace | KeyCode::Left => self.mode = Mode::Table,
+                KeyCode::Char('q') => return false,
+                code => move_selection(list_state, code, occurrences.len(), self.page_size),
+            },
+            Mode::Table => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return false,
+                KeyCode::Char('/') => self.mode = Mode::Search,
+                KeyCode::Char('r') => {
+                    let scales = Scale::value_variants();
+                    let index = scales.iter().position(|&scale| scale == self.scale).unwrap_or(0);
+                    self.scale = scales[(index + 1) % scales.len()];
+                    self.update_rows();
+                }
+                KeyCode::Char('s') => {
+                    self.ignore_stop_words = !self.ignore_stop_words;
+                    self.update_rows();
+                }
+                KeyCode::Enter | KeyCode::Right => self.show_occurrences(),
+                code => move_selection(&mut self.table_state, code, self.rows.len(), self.page_size),
+            },
+        }
+        true
+    }
+
+    fn show_occurrences(&mut self) {
+        if let Some(row) = self.table_state.selected().and_then(|i| self.rows.get(i)) {
+            let word = row.word.clone();
+            let occurrences = self.occurrences(&word);
+            let list_state = ListState::default().with_selected(Some(0));
+            self.mode = Mode::Occurrences { word, occurrences, list_state };
+        }
+    }
+
+    fn update_rows(&mut self) {
+        let counted: HashMap<String, usize> = self
+            .word_frequencies
+            .iter()
+            .filter(|(word, _)| !(self.ignore_stop_words && self.stop_words.contains(*word)))
+            .map(|(word, count)| (word.clone(), *count))
+            .collect();
+        self.rows = FrequencyCounter::sort_frequencies(&counted)
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| count.word.contains(&self.filter))
+            .map(|(i, count)| TableRow {
+                rank: i + 1,
+                word: count.word.to_string(),
+                count: count.count,
+                value: count.format(self.scale),
+            })
+            .collect();
+        self.table_state.select(if self.rows.is_empty() { None } else { Some(0) });
+    }
+
+    /// Runs the user interface in the terminal until the user quits.
+    pub fn run(mut self) -> Result<()> {
+        let mut terminal = ratatui::init();
+        let result = self.run_in(&mut terminal);
+        ratatui::restore();
+        result
+    }
+
+    fn run_in(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !self.handle_key(key)
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Draws the user interface in the given frame.
+    pub fn render(&mut self, frame: &mut Frame) {
+        let [header_area, main_area, footer_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        // Leave room for the borders and the table header
+        self.page_size = usize::from(main_area.height.saturating_sub(3)).max(1);
+
+        let total: usize = self.rows.iter().map(|row| row.count).sum();
+        let scale = self.scale.to_possible_value().expect("scales are not hidden");
+        let header = format!(
+            " {} | {} words shown, {} occurrences | counts: {} | stop words: {}",
+            self.title,
+            self.rows.len(),
+            total,
+            scale.get_name(),
+            if self.ignore_stop_words { "ignored" } else { "counted" },
+        );
+        frame.render_widget(Paragraph::new(header).reversed(), header_area);
+
+        let footer = match &mut self.mode {
+            Mode::Occurrences { word, occurrences, list_state } => {
+                let items: Vec<Line> = occurrences
+                    .iter()
+                    .map(|occurrence| {
+                        Line::from(vec![
+                            Span::raw(format!("{:>width$}", occurrence.before, width = CONTEXT_LENGTH)).dim(),
+                            Span::raw(occurrence.word.as_str()).bold().yellow(),
+                            Span::raw(occurrence.after.as_str()).dim(),
+                        ])
+                    })
+                    .collect();
+                let title = format!(" Occurrences of '{}' ({}) ", word, occurrences.len());
+                let list =
+                    List::new(items).block(Block::bordered().title(title)).highlight_style(Style::new().reversed());
+                frame.render_stateful_widget(list, main_area, list_state);
+                " ↑↓ move | Esc back | q quit".to_string()
+            }
+            mode => {
+                let rows = self.rows.iter().map(|row| {
+                    Row::new(vec![row.rank.to_string(), row.word.clone(), row.value.clone()])
+                });
+                let widths = [Constraint::Length(7), Constraint::Min(20), Constraint::Length(12)];
+                let table = Table::new(rows, widths)
+                    .header(Row::new(vec!["Rank", "Word", "Count"]).bold())
+                    .block(Block::bordered())
+                    .row_highlight_style(Style::new().reversed());
+                frame.render_stateful_widget(table, main_area, &mut self.table_state);
+                match mode {
+                    Mode::Search => format!(" Search: {}█ | Enter done | Esc clear", self.filter),
+                    _ if !self.filter.is_empty() => format!(
+                        " Search: '{}' | ↑↓ move | / search | r scale | s stop words | Enter occurrences | q quit",
+                        self.filter
+                    ),
+                    _ => " ↑↓ move | / search | r scale | s stop words | Enter occurrences | q quit".to_string(),
+                }
+            }
+        };
+        frame.render_widget(Paragraph::new(footer), footer_area);
+    }
+}
+
+/// Moves the selection of a table or list with `len` rows, given the key pressed.
+fn move_selection<S: Selectable>(state: &mut S, code: KeyCode, len: usize, page_size: usize) {
+    if len == 0 {
+        return;
+    }
+    let selected = state.selected_row().unwrap_or(0);
+    let new_selected = match code {
+        KeyCode::Up | KeyCode::Char('k') => selected.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => selected + 1,
+        KeyCode::PageUp => selected.saturating_sub(page_size),
+        KeyCode::PageDown => selected + page_size,
+        KeyCode::Home | KeyCode::Char('g') => 0,
+        KeyCode::End | KeyCode::Char('G') => len - 1,
+        _ => return,
+    };
+    state.select_row(Some(new_selected.min(len - 1)));
+}
+
+/// The state of a widget with selectable rows.
+trait Selectable {
+    fn selected_row(&self) -> Option<usize>;
+    fn select_row(&mut self, index: Option<usize>);
+}
+
+impl Selectable for TableState {
+    fn selected_row(&self) -> Option<usize> {
+        self.selected()
+    }
+
+    fn select_row(&mut self, index: Option<usize>) {
+        self.select(index);
+    }
+}
+
+impl Selectable for ListState {
+    fn selected_row(&self) -> Option<usize> {
+        self.selected()
+    }
+
+    fn select_row(&mut self, index: Option<usize>) {
+        self.select(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+
+    const TEXT: &str = "the cat sat on the mat.\nthe cat ate the rat, and the dog ate too.";
+
+    #[test]
+    fn test_rows() {
+        let app = app();
+        assert_eq!(words(&app), ["ate", "cat", "dog", "mat", "rat", "sat"]);
+        assert_eq!(app.rows()[1], TableRow { rank: 2, word: "cat".to_string(), count: 2, value: "2".to_string() });
+        assert_eq!(app.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_toggles() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.rows()[0], TableRow { rank: 1, word: "the".to_string(), count: 5, value: "5".to_string() });
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.rows()[1], TableRow { rank: 2, word: "ate".to_string(), count: 2, value: "40.00".to_string() });
+        press(&mut app, KeyCode::Char('s'));
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.rows()[0].value, "25.0000%");
+    }
+
+    #[test]
+    fn test_search() {
+        let mut app = app();
+        for key in [KeyCode::Char('/'), KeyCode::Char('a'), KeyCode::Char('t')] {
+            press(&mut app, key);
+        }
+        assert_eq!(words(&app), ["ate", "cat", "mat", "rat", "sat"]);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Char('c'));
+        assert_eq!(words(&app), ["cat"]);
+        assert_eq!(app.rows()[0].rank, 2);
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.rows()[0], TableRow { rank: 2, word: "cat".to_string(), count: 2, value: "100.00".to_string() });
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.rows().len(), 6);
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut app = app();
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected(), Some(2));
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.selected(), Some(1));
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected(), Some(5));
+        press(&mut app, KeyCode::PageUp);
+        assert_eq!(app.selected(), Some(0));
+        assert!(!app.handle_key(KeyEvent::from(KeyCode::Char('q'))));
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let app = app();
+        let occurrences = app.occurrences("cat");
+        assert_eq!(occurrences.len(), 2);
+        assert_eq!(
+            occurrences[1],
+            Occurrence {
+                before: "the cat sat on the mat. the ".to_string(),
+                word: "cat".to_string(),
+                after: " ate the rat, and the dog ate too.".to_string()
+            }
+        );
+        assert!(app.occurrences("ca").is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let mut app = app();
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen = screen_lines(&terminal);
+        assert!(screen[0].contains("test | 6 words shown, 8 occurrences | counts: absolute | stop words: ignored"));
+        assert!(screen[4].contains("2       cat"));
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let screen = screen_lines(&terminal);
+        assert!(screen[1].contains("Occurrences of 'cat' (2)"));
+        assert!(screen[2].contains("the cat sat on the mat."));
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(app.mode, Mode::Table));
+    }
+
+    fn app() -> App {
+        let stop_words = ["the", "on", "and", "too"].iter().map(|w| w.to_string()).collect();
+        App::new("test", TEXT.to_string(), stop_words, Scale::Absolute)
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        assert!(app.handle_key(KeyEvent::from(code)));
+    }
+
+    fn words(app: &App) -> Vec<&str> {
+        app.rows().iter().map(|row| row.word.as_str()).collect()
+    }
+
+    fn screen_lines(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    }
+}
@@ -0,0 +1,303 @@
+//!
+//! Contains code to cluster spelling variants and typos of words, so that, e.g., "recieve" is counted as "receive".
+//!
+//! The words are taken in descending order of frequency, and each word that is not yet part of a cluster becomes the
+//! canonical form of a new cluster, together with the less common words within the maximum edit distance of it. The
+//! words within the distance are found with a BK-tree, so that not every pair of words needs to be compared. A
+//! variant is only merged with the canonical form, never with other variants, so that clusters do not grow into
+//! chains of unrelated words.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use word_frequency::variants::VariantClusterer;
+//!
+//! let word_frequencies = HashMap::from([
+//!     ("receive".to_string(), 10),
+//!     ("recieve".to_string(), 3),
+//!     ("perceive".to_string(), 2),
+//!     ("cat".to_string(), 5),
+//!     ("hat".to_string(), 4),
+//! ]);
+//! let clusters = VariantClusterer::new(1).cluster(&word_frequencies);
+//! assert_eq!(clusters[0].canonical, "receive");
+//! assert_eq!(clusters[0].count, 13);
+//! assert_eq!(clusters[0].variants, [("recieve".to_string(), 3)]);
+//! assert_eq!(clusters.len(), 4);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::frequency::FrequencyCounter;
+
+/// The default length of the shortest words that are clustered, since short words that differ by a letter are
+/// usually different words, like "cat" and "hat".
+const DEFAULT_MIN_LENGTH: usize = 4;
+
+/// Clusters words that are within a maximum edit distance of a more common word.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct VariantClusterer {
+    max_distance: usize,
+    min_length: usize,
+}
+
+/// A canonical form of a word together with its variants.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Cluster {
+    /// The most common word in the cluster
+    pub canonical: String,
+    /// The number of occurrences of the canonical form and all its variants
+    pub count: usize,
+    /// The variants and their own counts, in descending order by count
+    pub variants: Vec<(String, usize)>,
+}
+
+impl VariantClusterer {
+    /// Creates a new `VariantClusterer` that clusters words within the given edit distance, where inserting,
+    /// deleting or substituting a character, or swapping two adjacent characters, counts as one edit.
+    pub fn new(max_distance: usize) -> Self {
+        Self {
+            max_distance,
+            min_length: DEFAULT_MIN_LENGTH,
+        }
+    }
+
+    /// Makes the `VariantClusterer` leave words with fewer characters than this alone, neither as canonical forms
+    /// nor as variants.
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Clusters the words of a mapping from words to frequencies as produced by
+    /// [`FrequencyCounter::count_frequencies()`]. Every word ends up in exactly one cluster, possibly without
+    /// variants. The clusters are sorted in descending order by their merged count.
+    pub fn cluster(&self, word_frequencies: &HashMap<String, usize>) -> Vec<Cluster> {
+        let sorted_frequencies = FrequencyCounter::sort_frequencies(word_frequencies);
+        let mut tree = BkTree::default();
+        for count in sorted_frequencies.iter().filter(|c| c.word.chars().count() >= self.min_length) {
+            tree.insert(count.word);
+        }
+
+        let mut clustered: HashSet<&str> = HashSet::new();
+        let mut clusters = Vec::new();
+        for count in &sorted_frequencies {
+            if !clustered.insert(count.word) {
+                continue;
+            }
+            let mut cluster = Cluster {
+                canonical: count.word.to_string(),
+                count: count.count,
+                variants: Vec::new(),
+            };
+            if count.word.chars().count() >= self.min_length {
+                let mut variants: Vec<&str> = tree.find(count.word, self.max_distance);
+                variants.retain(|variant| !clustered.contains(variant));
+                for variant in variants {
+                    clustered.insert(variant);
+                    let variant_count = word_frequencies[variant];
+                    cluster.count += variant_count;
+                    cluster.variants.push((variant.to_string(), variant_count));
+                }
+                cluster.variants.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            }
+            clusters.push(cluster);
+        }
+        clusters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.canonical.cmp(&b.canonical)));
+        clusters
+    }
+}
+
+/// Gives a mapping from the canonical form of each cluster to its merged count, which can be sorted with
+/// [`FrequencyCounter::sort_frequencies()`].
+pub fn merged_frequencies(clusters: &[Cluster]) -> HashMap<String, usize> {
+    clusters.iter().map(|cluster| (cluster.canonical.clone(), cluster.count)).collect()
+}
+
+/// Gives the Damerau–Levenshtein distance between two words, i.e., the number of characters that need to be
+/// inserted, deleted or substituted, or pairs of adjacent characters that need to be swapped, to turn one word into
+/// the other. Unlike the simpler optimal string alignment distance, characters may be edited again after a swap, e.g.,
+/// "ca" becomes "abc" by swapping and inserting, which makes the distance satisfy the triangle inequality that the
+/// BK-tree relies on.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // The table has an extra first row and column with a distance larger than any real one, so that a swap with no
+    // earlier match is never chosen, and row i + 1 and column j + 1 hold the distance between a[..i] and b[..j]
+    let width = b.len() + 2;
+    let infinity = a.len() + b.len();
+    let mut table = vec![infinity; (a.len() + 2) * width];
+    for i in 0..=a.len() {
+        table[(i + 1) * width + 1] = i;
+    }
+    for j in 0..=b.len() {
+        table[width + j + 1] = j;
+    }
+    // The last row of a where each character occurred, for finding the characters to swap
+    let mut last_row: HashMap<char, usize> = HashMap::new();
+    for i in 1..=a.len() {
+        let mut last_matching_column = 0;
+        for j in 1..=b.len() {
+            let k = last_row.get(&b[j - 1]).copied().unwrap_or(0);
+            let l = last_matching_column;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_matching_column = j;
+                0
+            } else {
+                1
+            };
+            let substitution = table[i * width + j] + cost;
+            let insertion = table[(i + 1) * width + j] + 1;
+            let deletion = table[i * width + j + 1] + 1;
+            let swap = table[k * width + l] + (i - k - 1) + 1 + (j - l - 1);
+            table[(i + 1) * width + j + 1] = substitution.min(insertion).min(deletion).min(swap);
+        }
+        last_row.insert(a[i - 1], i);
+    }
+    table[(a.len() + 1) * width + b.len() + 1]
+}
+
+/// A BK-tree, where the children of a node are keyed by their edit distance to it, so that a search can skip the
+/// subtrees that are too far away according to the triangle inequality.
+#[derive(Debug, Default)]
+struct BkTree<'a> {
+    nodes: Vec<BkNode<'a>>,
+}
+
+#[derive(Debug)]
+struct BkNode<'a> {
+    word: &'a str,
+    /// The indexes of the child nodes, keyed by their distance to this node
+    children: HashMap<usize, usize>,
+}
+
+impl<'a> BkTree<'a> {
+    fn insert(&mut self, word: &'a str) {
+        let new_index = self.nodes.len();
+        let mut index = 0;
+        while index < self.nodes.len() {
+            let distance = edit_distance(word, self.nodes[index].word);
+            if distance == 0 {
+                return;
+            }
+            index = *self.nodes[index].children.entry(distance).or_insert(new_index);
+        }
+        self.nodes.push(BkNode {
+            word,
+            children: HashMap::new(),
+        });
+    }
+
+    /// Gives the words within the given distance of the given word, except the word itself.
+    fn find(&self, word: &str, max_distance: usize) -> Vec<&'a str> {
+        let mut found = Vec::new();
+        let mut to_visit = if self.nodes.is_empty() { vec![] } else { vec![0] };
+        while let Some(index) = to_visit.pop() {
+            let node = &self.nodes[index];
+            let distance = edit_distance(word, node.word);
+            if distance > 0 && distance <= max_distance {
+                found.push(node.word);
+            }
+            let range = distance.saturating_sub(max_distance)..=distance + max_distance;
+            to_visit.extend(node.children.iter().filter(|(d, _)| range.contains(d)).map(|(_, &child)| child));
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("receive", "recieve"), 1);
+        assert_eq!(edit_distance("receive", "receive"), 0);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("ca", "abc"), 2);
+        assert_eq!(edit_distance("bba", "bbca"), 1);
+        assert_eq!(edit_distance("aab", "bbca"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("naïve", "naive"), 1);
+    }
+
+    #[test]
+    fn test_bk_tree_find() {
+        let words = ["book", "books", "cake", "boo", "boon", "cook", "cape", "cart"];
+        let mut tree = BkTree::default();
+        for word in words {
+            tree.insert(word);
+        }
+        tree.insert("book");
+        assert_eq!(tree.nodes.len(), words.len());
+        let mut found = tree.find("book", 1);
+        found.sort();
+        assert_eq!(found, ["boo", "books", "boon", "cook"]);
+        let mut found = tree.find("cake", 2);
+        found.sort();
+        assert_eq!(found, ["cape", "cart"]);
+    }
+
+    #[test]
+    fn test_bk_tree_finds_like_linear_scan() {
+        // All words of up to four letters from a small alphabet, which have many swaps and repeated letters
+        let mut words = vec![String::new()];
+        for length in 1..=4 {
+            let shorter: Vec<String> = words.iter().filter(|w| w.len() == length - 1).cloned().collect();
+            words.extend(shorter.iter().flat_map(|w| ["a", "b", "c"].map(|c| format!("{}{}", w, c))));
+        }
+        let mut tree = BkTree::default();
+        for word in &words {
+            tree.insert(word);
+        }
+        for word in &words {
+            for max_distance in 1..=2 {
+                let mut found = tree.find(word, max_distance);
+                found.sort();
+                let mut expected: Vec<&str> = words
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|other| (1..=max_distance).contains(&edit_distance(word, other)))
+                    .collect();
+                expected.sort();
+                assert_eq!(found, expected, "'{}' within {}", word, max_distance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cluster_does_not_chain() {
+        let word_frequencies =
+            HashMap::from([("abcd".to_string(), 5), ("abce".to_string(), 3), ("abfe".to_string(), 2)]);
+        let clusters = VariantClusterer::new(1).cluster(&word_frequencies);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].count, 8);
+        assert_eq!(clusters[1], Cluster { canonical: "abfe".to_string(), count: 2, variants: vec![] });
+
+        let clusters = VariantClusterer::new(2).cluster(&word_frequencies);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].variants, [("abce".to_string(), 3), ("abfe".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_cluster_finds_variants_after_swaps() {
+        // With the optimal string alignment distance, "aab" is 2 from "bba" but 4 from "bbca", so the BK-tree
+        // skipped "bbca" when looking for the variants of "bba"
+        let word_frequencies =
+            HashMap::from([("aab".to_string(), 3), ("bba".to_string(), 2), ("bbca".to_string(), 1)]);
+        let clusters = VariantClusterer::new(1).min_length(1).cluster(&word_frequencies);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[1].canonical, "bba");
+        assert_eq!(clusters[1].variants, [("bbca".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_min_length() {
+        let word_frequencies = HashMap::from([("cat".to_string(), 5), ("hat".to_string(), 3)]);
+        assert_eq!(VariantClusterer::new(1).cluster(&word_frequencies).len(), 2);
+        let clusters = VariantClusterer::new(1).min_length(3).cluster(&word_frequencies);
+        assert_eq!(merged_frequencies(&clusters), HashMap::from([("cat".to_string(), 8)]));
+    }
+}
@@ -0,0 +1,267 @@
+//!
+//! Contains code to count words by interning them, i.e., by giving each distinct word an integer ID, so that the
+//! counts can be kept in a vector indexed by ID instead of in a map with a string for every word.
+//!
+//! The words of a [`Vocabulary`] are stored one after another in a single string, which serves as an arena, and are
+//! looked up with the fast, non-cryptographic Fx hash instead of the SipHash that `HashMap` uses by default. The
+//! public API of [`FrequencyCounter`] that gives a `HashMap` from words to counts is built on top of [`WordCounts`].
+//! The counts are sorted as [`WordCount`]s, which refer to their words by ID, and a [`Count`] that borrows its word
+//! from the vocabulary is only a thin wrapper around one.
+//!
+//! # Examples
+//!
+//! ```
+//! use word_frequency::frequency::Count;
+//! use word_frequency::vocabulary::{WordCount, WordCounts};
+//!
+//! let mut word_counts = WordCounts::new();
+//! for word in "the cat and the hat".split(' ') {
+//!     word_counts.add(word);
+//! }
+//! assert_eq!(word_counts.count("the"), 2);
+//! let the = word_counts.vocabulary().get("the").unwrap();
+//! assert_eq!(word_counts.vocabulary().word(the), "the");
+//! assert_eq!(word_counts.count_of(the), 2);
+//! let sorted_ids = word_counts.sorted_ids();
+//! assert_eq!(sorted_ids[0], WordCount { id: the, count: 2 });
+//! assert_eq!(sorted_ids[0].resolve(word_counts.vocabulary()).word, "the");
+//! let sorted_counts = word_counts.sorted();
+//! assert_eq!(sorted_counts[0], Count::new("the", 2).with_shares(5, 2));
+//! assert_eq!(sorted_counts[1].word, "and");
+//! ```
+//!
+//! [`FrequencyCounter`]: crate::frequency::FrequencyCounter
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::ops::Range;
+
+use hashbrown::HashTable;
+use rustc_hash::FxBuildHasher;
+
+use crate::frequency::Count;
+
+/// The ID of a word in a [`Vocabulary`], given in the order the words were first seen, starting from 0.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct WordId(u32);
+
+impl WordId {
+    /// Gives the ID as an index, e.g., into a vector of counts.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A set of distinct words, each with a [`WordId`].
+#[derive(Debug, Default, Clone)]
+pub struct Vocabulary {
+    /// All words, one after another
+    arena: String,
+    /// The position of each word in the arena, indexed by ID
+    spans: Vec<Range<usize>>,
+    /// The IDs of the words, keyed by the hash of the word
+    ids: HashTable<WordId>,
+}
+
+impl Vocabulary {
+    /// Creates an empty `Vocabulary`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gives the ID of the given word, adding the word if it is not in the vocabulary yet.
+    pub fn intern(&mut self, word: &str) -> WordId {
+        let hash = FxBuildHasher.hash_one(word);
+        let Self { arena, spans, ids } = self;
+        if let Some(&id) = ids.find(hash, |&id| &arena[spans[id.index()].clone()] == word) {
+            return id;
+        }
+        let id = WordId(u32::try_from(spans.len()).expect("fewer than 2^32 distinct words"));
+        spans.push(arena.len()..arena.len() + word.len());
+        arena.push_str(word);
+        ids.insert_unique(hash, id, |&id| FxBuildHasher.hash_one(&arena[spans[id.index()].clone()]));
+        id
+    }
+
+    /// Gives the ID of the given word, if it is in the vocabulary.
+    pub fn get(&self, word: &str) -> Option<WordId> {
+        self.ids.find(FxBuildHasher.hash_one(word), |&id| self.word(id) == word).copied()
+    }
+
+    /// Gives the word with the given ID.
+    ///
+    /// Panics if the ID was given by another vocabulary and is too large for this one.
+    pub fn word(&self, id: WordId) -> &str {
+        &self.arena[self.spans[id.index()].clone()]
+    }
+
+    /// Gives the number of distinct words.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Tells if the vocabulary has no words.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Gives the words and their IDs, in the order of the IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (WordId, &str)> {
+        (0..self.spans.len() as u32).map(|i| (WordId(i), self.word(WordId(i))))
+    }
+}
+
+/// A word, given by its ID in a [`Vocabulary`], and its number of occurrences.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct WordCount {
+    pub id: WordId,
+    pub count: usize,
+}
+
+impl WordCount {
+    /// Gives the count with the word borrowed from the given vocabulary, which must be the one that gave the ID.
+    pub fn resolve(self, vocabulary: &Vocabulary) -> Count<'_> {
+        Count::new(vocabulary.word(self.id), self.count)
+    }
+}
+
+/// The number of occurrences of each word in a [`Vocabulary`].
+#[derive(Debug, Default, Clone)]
+pub struct WordCounts {
+    vocabulary: Vocabulary,
+    /// The count of each word, indexed by ID
+    counts: Vec<usize>,
+}
+
+impl WordCounts {
+    /// Creates empty `WordCounts`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts one more occurrence of the given word, and gives its ID.
+    pub fn add(&mut self, word: &str) -> WordId {
+        let id = self.vocabulary.intern(word);
+        if id.index() == self.counts.len() {
+            self.counts.push(0);
+        }
+        self.counts[id.index()] += 1;
+        id
+    }
+
+    /// Gives the vocabulary of the counted words.
+    pub fn vocabulary(&self) -> &Vocabulary {
+        &self.vocabulary
+    }
+
+    /// Gives the number of occurrences of the given word.
+    pub fn count(&self, word: &str) -> usize {
+        self.vocabulary.get(word).map_or(0, |id| self.count_of(id))
+    }
+
+    /// Gives the number of occurrences of the word with the given ID.
+    pub fn count_of(&self, id: WordId) -> usize {
+        self.counts.get(id.index()).copied().unwrap_or(0)
+    }
+
+    /// Gives the words and their counts, in the order of the IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.vocabulary.iter().map(|(id, word)| (word, self.counts[id.index()]))
+    }
+
+    /// Gives the counts by ID, sorted by descending count and then by word, like [`Self::sorted()`].
+    pub fn sorted_ids(&self) -> Vec<WordCount> {
+        let mut sorted_ids: Vec<WordCount> =
+            self.counts.iter().enumerate().map(|(i, &count)| WordCount { id: WordId(i as u32), count }).collect();
+        sorted_ids.sort_unstable_by(|a, b| {
+            b.count.cmp(&a.count).then_with(|| self.vocabulary.word(a.id).cmp(self.vocabulary.word(b.id)))
+        });
+        sorted_ids
+    }
+
+    /// Gives the counts sorted like [`FrequencyCounter::sort_frequencies()`] does, with the words borrowed from the
+    /// vocabulary.
+    ///
+    /// [`FrequencyCounter::sort_frequencies()`]: crate::frequency::FrequencyCounter::sort_frequencies()
+    pub fn sorted(&self) -> Vec<Count<'_>> {
+        let total = self.counts.iter().sum();
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        self.sorted_ids()
+            .into_iter()
+            .map(|word_count| word_count.resolve(&self.vocabulary).with_shares(total, max))
+            .collect()
+    }
+
+    /// Adds the counts to the given mapping from words to counts, e.g., to give the result of
+    /// [`FrequencyCounter::count_frequencies()`].
+    ///
+    /// [`FrequencyCounter::count_frequencies()`]: crate::frequency::FrequencyCounter::count_frequencies()
+    pub fn add_to(&self, word_frequencies: &mut HashMap<String, usize>) {
+        word_frequencies.reserve(self.counts.len());
+        for (word, count) in self.iter() {
+            match word_frequencies.get_mut(word) {
+                Some(total) => *total += count,
+                None => {
+                    word_frequencies.insert(word.to_string(), count);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut vocabulary = Vocabulary::new();
+        let cat = vocabulary.intern("cat");
+        let hat = vocabulary.intern("hat");
+        assert_eq!(vocabulary.intern("cat"), cat);
+        assert_eq!((cat.index(), hat.index()), (0, 1));
+        assert_eq!(vocabulary.word(hat), "hat");
+        assert_eq!(vocabulary.get("hat"), Some(hat));
+        assert_eq!(vocabulary.get("ca"), None);
+        assert_eq!(vocabulary.len(), 2);
+        assert_eq!(vocabulary.iter().collect::<Vec<_>>(), [(cat, "cat"), (hat, "hat")]);
+    }
+
+    #[test]
+    fn test_many_words() {
+        let mut word_counts = WordCounts::new();
+        for i in 0..10_000 {
+            word_counts.add(&format!("word{}", i % 1000));
+        }
+        assert_eq!(word_counts.vocabulary().len(), 1000);
+        assert!((0..1000).all(|i| word_counts.count(&format!("word{}", i)) == 10));
+        assert_eq!(word_counts.count("word1000"), 0);
+    }
+
+    #[test]
+    fn test_sorted_ids() {
+        let mut word_counts = WordCounts::new();
+        for word in "b c a c b c".split(' ') {
+            word_counts.add(word);
+        }
+        let ids: Vec<(&str, usize)> = word_counts
+            .sorted_ids()
+            .into_iter()
+            .map(|word_count| (word_counts.vocabulary().word(word_count.id), word_count.count))
+            .collect();
+        assert_eq!(ids, [("c", 3), ("b", 2), ("a", 1)]);
+        let sorted: Vec<(&str, usize)> = word_counts.sorted().iter().map(|count| (count.word, count.count)).collect();
+        assert_eq!(sorted, ids);
+    }
+
+    #[test]
+    fn test_add_to() {
+        let mut word_counts = WordCounts::new();
+        word_counts.add("cat");
+        word_counts.add("cat");
+        word_counts.add("hat");
+        let mut word_frequencies = HashMap::from([("cat".to_string(), 1)]);
+        word_counts.add_to(&mut word_frequencies);
+        assert_eq!(word_frequencies, HashMap::from([("cat".to_string(), 3), ("hat".to_string(), 1)]));
+    }
+}
@@ -0,0 +1,98 @@
+//!
+//! Contains the WebAssembly bindings of the library, for counting words in a browser. Needs the `wasm` feature, and is
+//! built for the `wasm32-unknown-unknown` target with wasm-pack, which also generates the JavaScript glue code:
+//!
+//! ```text
+//! wasm-pack build --target web --no-default-features --features wasm
+//! ```
+//!
+//! There is no file system in the browser, so the functions that read files, like `FrequencyCounter::from_file()`,
+//! are left out of the WebAssembly build, as are memory mapping and zstd decompression. The text and the stop words
+//! are given as strings instead:
+//!
+//! ```js
+//! import init, { countFrequencies } from "./pkg/word_frequency.js";
+//!
+//! await init();
+//! const result = JSON.parse(countFrequencies("The cat and the hat", "the, and", 1, 25));
+//! // { total: 2, frequencies: [{ word: "cat", count: 1, percent: 50, per_million: 500000, relative: 100 }, ...] }
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_json::Value;
+//! use word_frequency::wasm;
+//!
+//! let json = wasm::count_frequencies("The cat and the hat", "the, and", 1, 25);
+//! let result: Value = serde_json::from_str(&json).unwrap();
+//! assert_eq!(result["total"], 2);
+//! assert_eq!(result["frequencies"][0]["word"], "cat");
+//! ```
+
+use std::collections::HashMap;
+
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+use crate::frequency::{Count, FrequencyCounter};
+
+/// Counts the words of the given text, or the sequences of `ngrams` words, ignoring the comma-separated stop words,
+/// and gives the `num_words` most common as a JSON object like the one the HTTP server gives. The text and the stop
+/// words are converted to lower case.
+#[wasm_bindgen(js_name = countFrequencies)]
+pub fn count_frequencies(text: &str, stop_words: &str, ngrams: usize, num_words: usize) -> String {
+    let mut frequency_counter = FrequencyCounter::new(&text.to_lowercase());
+    frequency_counter.set_stop_words(
+        stop_words.split(',').map(str::trim).filter(|word| !word.is_empty()).map(str::to_lowercase).collect(),
+    );
+    let frequencies = frequency_counter.count_ngrams(ngrams.max(1));
+    frequencies_json(&frequencies, num_words)
+}
+
+/// Sorts the words of a JSON object from words to counts, like `{"cat": 1, "the": 2}`, and gives the `num_words` most
+/// common like [`count_frequencies()`], e.g., to combine the counts of several texts. Counts that are not
+/// non-negative integers are ignored.
+#[wasm_bindgen(js_name = sortFrequencies)]
+pub fn sort_frequencies(frequencies: &str, num_words: usize) -> Result<String, JsError> {
+    let frequencies: HashMap<String, serde_json::Value> = serde_json::from_str(frequencies)?;
+    let frequencies: HashMap<String, usize> = frequencies
+        .into_iter()
+        .filter_map(|(word, count)| Some((word, usize::try_from(count.as_u64()?).ok()?)))
+        .collect();
+    Ok(frequencies_json(&frequencies, num_words))
+}
+
+fn frequencies_json(frequencies: &HashMap<String, usize>, num_words: usize) -> String {
+    let sorted_frequencies = FrequencyCounter::sort_frequencies(frequencies);
+    let counts: Vec<serde_json::Value> = sorted_frequencies.iter().take(num_words).map(Count::to_json).collect();
+    json!({ "total": frequencies.values().sum::<usize>(), "frequencies": counts }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_count_frequencies() {
+        let json = count_frequencies("To be, or not to BE, to be", "or", 2, 10);
+        let result: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(result["total"], 4);
+        assert_eq!(
+            result["frequencies"][0],
+            json!({ "word": "to be", "count": 3, "percent": 75.0, "per_million": 750000.0, "relative": 100.0 })
+        );
+        assert_eq!(result["frequencies"][1]["word"], "not to");
+    }
+
+    #[test]
+    fn test_sort_frequencies() {
+        let json = sort_frequencies(r#"{"cat": 1, "the": 3, "hat": 1, "bad": -1}"#, 2).unwrap();
+        let result: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(result["total"], 5);
+        let words: Vec<&Value> = result["frequencies"].as_array().unwrap().iter().map(|count| &count["word"]).collect();
+        assert_eq!(words, ["the", "cat"]);
+    }
+}